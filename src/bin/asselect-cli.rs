@@ -0,0 +1,297 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use asselect::convert::{cub, geojson, kml, openair, tnp, OutputOptions};
+use asselect::settings::{AirType, Format, Overlay, Override, Settings};
+use asselect::yaixm::{Level, Yaixm};
+use chrono::{DateTime, Utc};
+use clap::Parser;
+
+// Command line airspace generator
+#[derive(Parser)]
+#[command(version, about = "Generate UK airspace files from YAIXM data")]
+struct Args {
+    /// YAIXM data file
+    yaixm: PathBuf,
+
+    /// Settings JSON file, as exported by the web app. Options below
+    /// override values from the file
+    #[arg(short, long)]
+    settings: Option<PathBuf>,
+
+    /// Output file [default: stdout]
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Output format: openair, openair_ext, ratonly, competition, geojson,
+    /// tnp, kml or cub
+    #[arg(short, long, value_parser = parse_format)]
+    format: Option<Format>,
+
+    /// ATZ type: classd or ctr
+    #[arg(long, value_parser = parse_airtype)]
+    atz: Option<AirType>,
+
+    /// ILS feather type, or none
+    #[arg(long, value_parser = parse_airtype_or_none)]
+    ils: Option<AirTypeArg>,
+
+    /// Non-ATZ airfield type, or none
+    #[arg(long, value_parser = parse_airtype_or_none)]
+    unlicensed: Option<AirTypeArg>,
+
+    /// Microlight airfield type, or none
+    #[arg(long, value_parser = parse_airtype_or_none)]
+    microlight: Option<AirTypeArg>,
+
+    /// Gliding site type, or none
+    #[arg(long, value_parser = parse_airtype_or_none)]
+    gliding: Option<AirTypeArg>,
+
+    /// Home gliding site, excluded from the output
+    #[arg(long)]
+    home: Option<String>,
+
+    /// HIRTA/GVS/laser type, or none
+    #[arg(long, value_parser = parse_airtype_or_none)]
+    hirta_gvs: Option<AirTypeArg>,
+
+    /// Obstacle type, or none
+    #[arg(long, value_parser = parse_airtype_or_none)]
+    obstacle: Option<AirTypeArg>,
+
+    /// Maximum level, e.g. FL195 or 4500 ft
+    #[arg(long)]
+    max_level: Option<Level>,
+
+    /// Minimum level, e.g. FL50 or 3000 ft
+    #[arg(long)]
+    min_level: Option<Level>,
+
+    /// Clip upper limits at the maximum level, or not with =false
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    clip_upper: Option<bool>,
+
+    /// Append radio frequency to names, or not with =false
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    radio: Option<bool>,
+
+    /// Altitude overlay named in the header: fl195, fl105 or atzdz
+    #[arg(long, value_parser = parse_overlay)]
+    overlay: Option<Overlay>,
+
+    /// Overlay file appended to OpenAir output (overlays are not generated
+    /// from YAIXM data)
+    #[arg(long)]
+    overlay_file: Option<PathBuf>,
+
+    /// Local agreement to include (may be repeated)
+    #[arg(long)]
+    loa: Vec<String>,
+
+    /// Temporary restriction to include (may be repeated)
+    #[arg(long)]
+    rat: Vec<String>,
+
+    /// Wave box to include (may be repeated)
+    #[arg(long)]
+    wave: Vec<String>,
+
+    /// Feature id (or name) to always include (may be repeated)
+    #[arg(long)]
+    include: Vec<String>,
+
+    /// Feature id (or name) to always exclude (may be repeated)
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    /// User agent recorded in the file header [default: asselect-cli/VERSION]
+    #[arg(long)]
+    user_agent: Option<String>,
+
+    /// Production time recorded in the file header, RFC 3339 format
+    /// [default: now]
+    #[arg(long)]
+    timestamp: Option<DateTime<Utc>>,
+
+    /// Sort features by name and omit the production time and user agent
+    /// from the header, unless given
+    #[arg(long)]
+    reproducible: bool,
+}
+
+// User agent recorded by default
+const USER_AGENT: &str = concat!("asselect-cli/", env!("CARGO_PKG_VERSION"));
+
+// Command line value parsers
+fn parse_format(code: &str) -> Result<Format, String> {
+    Format::from_code(code).ok_or_else(|| format!("unknown format \"{}\"", code))
+}
+
+fn parse_overlay(code: &str) -> Result<Overlay, String> {
+    Overlay::from_code(code).ok_or_else(|| format!("unknown overlay \"{}\"", code))
+}
+
+fn parse_airtype(code: &str) -> Result<AirType, String> {
+    AirType::from_code(code).ok_or_else(|| format!("unknown airspace type \"{}\"", code))
+}
+
+// Optional airspace type, "none" to exclude
+#[derive(Clone)]
+struct AirTypeArg(Option<AirType>);
+
+fn parse_airtype_or_none(code: &str) -> Result<AirTypeArg, String> {
+    match code {
+        "none" => Ok(AirTypeArg(None)),
+        _ => parse_airtype(code).map(|a| AirTypeArg(Some(a))),
+    }
+}
+
+// Settings from file, with command line options applied
+fn settings(args: &Args) -> Result<Settings, String> {
+    let mut settings = match &args.settings {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+            let value = serde_json::from_str(&text)
+                .map_err(|e| format!("can't parse {}: {}", path.display(), e))?;
+            let (settings, warnings) = Settings::migrate(value);
+            for warning in warnings {
+                eprintln!("warning: {}", warning);
+            }
+            settings
+        }
+        None => Settings::default(),
+    };
+
+    if let Some(format) = &args.format {
+        settings.format = format.clone();
+    }
+    if let Some(atz) = args.atz {
+        settings.atz = atz;
+    }
+    let airtypes = [
+        (&args.ils, &mut settings.ils),
+        (&args.unlicensed, &mut settings.unlicensed),
+        (&args.microlight, &mut settings.microlight),
+        (&args.gliding, &mut settings.gliding),
+        (&args.hirta_gvs, &mut settings.hirta_gvs),
+        (&args.obstacle, &mut settings.obstacle),
+    ];
+    for (arg, setting) in airtypes {
+        if let Some(AirTypeArg(airtype)) = arg {
+            *setting = *airtype;
+        }
+    }
+    if args.home.is_some() {
+        settings.home = args.home.clone();
+    }
+    if let Some(max_level) = args.max_level {
+        settings.max_level = max_level;
+    }
+    if let Some(min_level) = args.min_level {
+        settings.min_level = min_level;
+    }
+    if let Some(clip_upper) = args.clip_upper {
+        settings.clip_upper = clip_upper;
+    }
+    if let Some(radio) = args.radio {
+        settings.radio = radio;
+    }
+    if args.overlay.is_some() {
+        settings.overlay = args.overlay;
+    }
+    settings.loa.extend(args.loa.iter().cloned());
+    settings.rat.extend(args.rat.iter().cloned());
+    settings.wave.extend(args.wave.iter().cloned());
+    for key in &args.include {
+        settings.overrides.insert(key.clone(), Override::Include);
+    }
+    for key in &args.exclude {
+        settings.overrides.insert(key.clone(), Override::Exclude);
+    }
+
+    Ok(settings)
+}
+
+// Generate airspace data in the selected format
+fn generate(args: &Args) -> Result<Vec<u8>, String> {
+    let text = fs::read_to_string(&args.yaixm)
+        .map_err(|e| format!("can't read {}: {}", args.yaixm.display(), e))?;
+    let yaixm: Yaixm = serde_json::from_str(&text)
+        .map_err(|e| format!("can't parse {}: {}", args.yaixm.display(), e))?;
+
+    let mut settings = settings(args)?;
+    for dropped in settings.retain_known(&yaixm) {
+        eprintln!("warning: {} not found, ignored", dropped);
+    }
+
+    let options = if args.reproducible {
+        OutputOptions {
+            timestamp: args.timestamp,
+            user_agent: args.user_agent.clone(),
+            reproducible: true,
+        }
+    } else {
+        OutputOptions {
+            timestamp: Some(args.timestamp.unwrap_or_else(Utc::now)),
+            user_agent: Some(args.user_agent.as_deref().unwrap_or(USER_AGENT).to_string()),
+            reproducible: false,
+        }
+    };
+
+    let result = match settings.format {
+        Format::Cub => cub(&yaixm, &settings, &options),
+        Format::GeoJson => geojson(&yaixm, &settings, &options).map(String::into_bytes),
+        Format::Kml => kml(&yaixm, &settings, &options).map(String::into_bytes),
+        Format::Tnp => tnp(&yaixm, &settings, &options).map(String::into_bytes),
+        _ => {
+            let overlay = match &args.overlay_file {
+                Some(path) => fs::read_to_string(path)
+                    .map_err(|e| format!("can't read {}: {}", path.display(), e))?,
+                None => String::new(),
+            };
+            openair(&yaixm, &settings, &options).map(|oa| (oa + &overlay).into_bytes())
+        }
+    };
+
+    result.map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let result = generate(&args).and_then(|data| match &args.output {
+        Some(path) => {
+            fs::write(path, data).map_err(|e| format!("can't write {}: {}", path.display(), e))
+        }
+        None => io::stdout()
+            .write_all(&data)
+            .map_err(|e| format!("can't write output: {}", e)),
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -17,6 +17,11 @@ pub mod about_tab;
 pub mod airspace_tab;
 pub mod extra_panel;
 pub mod extra_tab;
+pub mod level_field;
+pub mod map_tab;
 pub mod notam_tab;
 pub mod options_tab;
+pub mod override_list;
+pub mod profile_bar;
+pub mod region_settings;
 pub mod tabs;
@@ -22,6 +22,18 @@ pub fn about_tab() -> Html {
           <h3 class="subtitle">{ "Output Format" }</h3>
             <p>{ "ASSelect generates airspace data for the mainland UK in OpenAir format.
               \"Competition\" format is intended for competition organiser use only." }</p>
+            <p>{ "\"OpenAir (extended)\" adds the AY (type) and AG (ground station) records
+              understood by recent versions of XCSoar and LK8000, with AC giving the
+              ICAO class. The airspace type options apply to the AC and AY records in
+              the same way as for OpenAir. The AX (transponder code) record is not
+              supported as the source data does not include transponder codes." }</p>
+            <p>{ "GeoJSON output is intended for web mapping. Arcs and circles are
+              converted to polygons." }</p>
+            <p>{ "KML output shows each airspace volume in 3D in Google Earth. Flight
+              levels are converted to altitude using standard pressure." }</p>
+            <p>{ "CUB output is for Naviter SeeYou and Oudie." }</p>
+            <p>{ "TNP (Tim Newport-Peace) output is provided for older flight computers
+              and scoring programs." }</p>
           <h3 class="subtitle">{ "Airspace Types" }</h3>
             <p><em>{ "Non-ATZ Airfields" }</em>{ " are busy training aerodromes (without an ATZ) as listed
               in section ENR\u{00a0}5.5 of the AIP. Note, the AIP does not include the majority
@@ -55,6 +67,22 @@ pub fn about_tab() -> Html {
           <h3 class="subtitle">{ "Altitude Overlay" }</h3>
             <p>{ "ASSelect can generate a graphical overlay showing the base of controlled airspace.
               The overlay is encoded as Class B airspace." }</p>
+          <h3 class="subtitle">{ "Airspace Overrides" }</h3>
+            <p>{ "Individual airspace can be always included or always excluded,
+              regardless of the airspace type settings, from the Airspace Overrides
+              section of the Extra tab. Level and region filters still apply." }</p>
+          <h3 class="subtitle">{ "Levels" }</h3>
+            <p>{ "Maximum and minimum levels can be any flight level (e.g. FL85) or
+              altitude (e.g. 4500 ft). Airspace with a base at or above the maximum
+              level is removed, comparing altitudes with flight levels at standard
+              pressure (so a 6500 ft base is removed at FL65). Airspace with a top
+              below the minimum level is removed only if it is clear of the level
+              with QNH anywhere between 950 and 1050 hPa." }</p>
+          <h3 class="subtitle">{ "Region" }</h3>
+            <p>{ "Airspace can be limited to a region given by a centre and radius, a
+              bounding box or a GeoJSON polygon. Airspace entirely outside the region
+              is removed. Optionally airspace can be clipped at the region boundary,
+              but only for convex regions (circles, boxes and convex polygons)." }</p>
           <h3 class="subtitle">{ "Data" }</h3>
             <p>{ "Airspace data is updated every four weeks - see " }
               <a class="text-primary"
@@ -13,8 +13,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 //
-use crate::state::{AirType, Settings};
-use crate::AirspaceSetting;
+use crate::state::Action;
+use asselect::settings::{AirType, Settings};
 use web_sys::HtmlInputElement;
 use yew::{function_component, html, Callback, Event, Html, Properties, TargetCast};
 
@@ -22,18 +22,17 @@ use yew::{function_component, html, Callback, Event, Html, Properties, TargetCas
 pub struct Props {
     pub settings: Settings,
     pub gliding_sites: Vec<String>,
-    pub callback: Callback<AirspaceSetting>,
+    pub callback: Callback<Action>,
 }
 
 #[function_component(AirspaceTab)]
 pub fn airspace_tab(props: &Props) -> Html {
-    let onchange = props.callback.reform(|e: Event| {
-        let element = e.target_unchecked_into::<HtmlInputElement>();
-        let name = element.name();
-        let value = element.value();
-
-        AirspaceSetting { name, value }
-    });
+    // Convert selected value to settings action
+    let onchange = |action: fn(String) -> Action| {
+        props
+            .callback
+            .reform(move |e: Event| action(e.target_unchecked_into::<HtmlInputElement>().value()))
+    };
 
     let set = &props.settings;
 
@@ -58,7 +57,7 @@ pub fn airspace_tab(props: &Props) -> Html {
                   {"ATZ"}
                   <div class="control">
                     <div class="select is-fullwidth">
-                      <select name="atz" onchange={onchange.clone()}>
+                      <select name="atz" onchange={onchange(|v| Action::SetAtz(AirType::from_code(&v).unwrap_or(AirType::Ctr)))}>
                         <option value="classd" selected={set.atz == AirType::ClassD}>{"Class D"}</option>
                         <option value="ctr" selected={set.atz == AirType::Ctr}>{"Control Zone"}</option>
                       </select>
@@ -74,7 +73,7 @@ pub fn airspace_tab(props: &Props) -> Html {
                   {"ILS Feather"}
                   <div class="control">
                     <div class="select is-fullwidth">
-                      <select name="ils" onchange={onchange.clone()}>
+                      <select name="ils" onchange={onchange(|v| Action::SetIls(AirType::from_code(&v)))}>
                         <option value="atz" selected={set.ils.is_none()}>{"As ATZ"}</option>
                         <option value="classf" selected={set.ils == Some(AirType::ClassF)}>{"Class F"}</option>
                         <option value="classg" selected={set.ils == Some(AirType::ClassG)}>{"Class G"}</option>
@@ -93,7 +92,7 @@ pub fn airspace_tab(props: &Props) -> Html {
                   {"Non-ATZ Airfield"}
                   <div class="control">
                     <div class="select is-fullwidth">
-                      <select name="unlicensed" onchange={onchange.clone()}>
+                      <select name="unlicensed" onchange={onchange(|v| Action::SetUnlicensed(AirType::from_code(&v)))}>
                         <option value="exclude" selected={set.unlicensed.is_none()}>{"No"}</option>
                         <option value="classf" selected={set.unlicensed == Some(AirType::ClassF)}>{"Class F"}</option>
                         <option value="classg" selected={set.unlicensed == Some(AirType::ClassG)}>{"Class G"}</option>
@@ -110,7 +109,7 @@ pub fn airspace_tab(props: &Props) -> Html {
                   {"Microlight Airfield"}
                   <div class="control">
                     <div class="select is-fullwidth">
-                      <select name="microlight" onchange={onchange.clone()}>
+                      <select name="microlight" onchange={onchange(|v| Action::SetMicrolight(AirType::from_code(&v)))}>
                         <option value="exclude" selected={set.microlight.is_none()}>{"No"}</option>
                         <option value="classf" selected={set.microlight == Some(AirType::ClassF)}>{"Class F"}</option>
                         <option value="classg" selected={set.microlight == Some(AirType::ClassG)}>{"Class G"}</option>
@@ -129,7 +128,7 @@ pub fn airspace_tab(props: &Props) -> Html {
                   {"Gliding Airfield"}
                   <div class="control">
                     <div class="select is-fullwidth">
-                      <select name="gliding" onchange={onchange.clone()}>
+                      <select name="gliding" onchange={onchange(|v| Action::SetGliding(AirType::from_code(&v)))}>
                         <option value="exclude" selected={set.gliding.is_none()}>{"No"}</option>
                         <option value="gsec" selected={set.gliding == Some(AirType::Gliding)}>{"Gliding Sector"}</option>
                         <option value="classf" selected={set.gliding == Some(AirType::ClassF)}>{"Class F"}</option>
@@ -147,7 +146,7 @@ pub fn airspace_tab(props: &Props) -> Html {
                   {"Exclude Home Airfield"}
                   <div class="control">
                     <div class="select is-fullwidth">
-                      <select name="home" onchange={onchange.clone()}>
+                      <select name="home" onchange={onchange(|v| Action::SetHome((v != "no").then_some(v)))}>
                         <option value="no" selected={set.home.is_none()}>{"No"}</option>
                         { gliding_sites() }
                       </select>
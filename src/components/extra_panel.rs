@@ -14,7 +14,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 //
 use crate::{ExtraCategory, ExtraSetting};
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 use web_sys::HtmlInputElement;
 use yew::{function_component, html, Callback, Event, Html, Properties, TargetCast};
 
@@ -22,7 +22,7 @@ use yew::{function_component, html, Callback, Event, Html, Properties, TargetCas
 pub struct Props {
     pub category: ExtraCategory,
     pub names: Vec<String>,
-    pub selected: HashSet<String>,
+    pub selected: BTreeSet<String>,
     pub callback: Callback<ExtraSetting>,
 }
 
@@ -0,0 +1,118 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use asselect::yaixm::Level;
+use web_sys::HtmlInputElement;
+use yew::{
+    function_component, html, use_effect_with, use_state, AttrValue, Callback, Event, Html,
+    Properties, TargetCast,
+};
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub label: AttrValue,
+    pub level: Level,
+    pub presets: Vec<(Level, AttrValue)>,
+    pub callback: Callback<Level>,
+}
+
+#[function_component(LevelField)]
+pub fn level_field(props: &Props) -> Html {
+    // Custom chosen from the menu, reset when the level changes
+    let custom_selected = use_state(|| false);
+    let error = use_state(|| false);
+
+    {
+        let custom_selected = custom_selected.clone();
+        let error = error.clone();
+        use_effect_with(props.level, move |_| {
+            custom_selected.set(false);
+            error.set(false);
+            || ()
+        });
+    }
+
+    let custom = *custom_selected || !props.presets.iter().any(|(l, _)| *l == props.level);
+
+    let onselect = {
+        let custom = custom_selected.clone();
+        let presets = props.presets.clone();
+        let callback = props.callback.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<HtmlInputElement>().value();
+            match value.parse::<usize>().ok().and_then(|n| presets.get(n)) {
+                Some((level, _)) => {
+                    custom.set(false);
+                    callback.emit(*level);
+                }
+                None => custom.set(true),
+            }
+        })
+    };
+
+    let oninput = {
+        let error = error.clone();
+        let callback = props.callback.clone();
+        Callback::from(move |e: Event| {
+            match e
+                .target_unchecked_into::<HtmlInputElement>()
+                .value()
+                .parse()
+            {
+                Ok(level) => {
+                    error.set(false);
+                    callback.emit(level);
+                }
+                Err(_) => error.set(true),
+            }
+        })
+    };
+
+    let options = props
+        .presets
+        .iter()
+        .enumerate()
+        .map(|(n, (level, name))| {
+            html! {
+                <option value={n.to_string()} selected={!custom && *level == props.level}>{ name }</option>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <div class="field">
+          <label class="label">
+            { &props.label }
+            <div class="control">
+              <div class="select is-fullwidth">
+                <select onchange={onselect}>
+                  { options }
+                  <option value="custom" selected={custom}>{"Custom"}</option>
+                </select>
+              </div>
+            </div>
+          </label>
+          if custom {
+            <div class="control mt-2">
+              <input class="input" type="text" placeholder="e.g. FL85 or 4500 ft"
+                value={props.level.to_string()} onchange={oninput} />
+            </div>
+            if *error {
+              <p class="help is-danger">{"Enter a flight level (FL85) or altitude (4500 ft)"}</p>
+            }
+          }
+        </div>
+    }
+}
@@ -0,0 +1,437 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gloo::file::{futures::read_as_text, Blob};
+use web_sys::{Element, HtmlInputElement};
+use yew::{
+    function_component, html, use_effect_with, use_memo, use_mut_ref, use_node_ref, use_state,
+    Callback, Event, Html, MouseEvent, Properties, TargetCast, WheelEvent,
+};
+
+use asselect::convert::{map_volumes, region_polygon, type_colour, MapVolume};
+use asselect::geometry::{boundary_points, DEFAULT_CHORD_ERROR};
+use asselect::openair::read;
+use asselect::settings::Settings;
+use asselect::yaixm::{LatLon, Yaixm};
+
+// Zoom step for buttons and mouse wheel
+const ZOOM_STEP: f64 = 1.25;
+
+#[derive(Properties)]
+pub struct Props {
+    pub yaixm: Rc<Yaixm>,
+    pub settings: Settings,
+    // Map is only computed when its tab is visible
+    pub active: bool,
+}
+
+// YAIXM data is never modified once loaded, so compare by pointer
+impl PartialEq for Props {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.yaixm, &other.yaixm)
+            && self.settings == other.settings
+            && self.active == other.active
+    }
+}
+
+// SVG view box, in nautical miles from the map origin
+#[derive(Clone, Copy, PartialEq)]
+struct View {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+impl View {
+    fn zoom(&self, factor: f64) -> View {
+        let width = self.width / factor;
+        let height = self.height / factor;
+        View {
+            x: self.x + (self.width - width) / 2.0,
+            y: self.y + (self.height - height) / 2.0,
+            width,
+            height,
+        }
+    }
+}
+
+// Projected volumes, with the view box enclosing them all
+struct MapData {
+    volumes: Result<Vec<MapVolume>, String>,
+    paths: Vec<String>,
+    compare: Vec<String>,
+    region: Option<String>,
+    view: View,
+}
+
+// Uploaded OpenAir file for comparison, as polygons
+#[derive(PartialEq)]
+struct Comparison {
+    name: String,
+    polygons: Vec<Vec<LatLon>>,
+}
+
+// Read OpenAir file into polygons
+fn read_comparison(name: String, text: &str) -> Result<Comparison, String> {
+    let features = read(text).map_err(|e| e.to_string())?;
+    let polygons = features
+        .iter()
+        .flat_map(|f| f.geometry.iter())
+        .map(|v| boundary_points(&v.boundary, DEFAULT_CHORD_ERROR * 5.0))
+        .collect::<Result<Vec<Vec<LatLon>>, &str>>()?;
+
+    Ok(Comparison { name, polygons })
+}
+
+// Local equirectangular projection, in nautical miles
+struct Projection {
+    lat0: f64,
+    lon0: f64,
+    scale: f64,
+}
+
+impl Projection {
+    fn project(&self, p: &LatLon) -> (f64, f64) {
+        (
+            (p.lon - self.lon0) * 60.0 * self.scale,
+            (self.lat0 - p.lat) * 60.0,
+        )
+    }
+}
+
+fn map_data(yaixm: &Yaixm, settings: &Settings, compare: Option<&Comparison>) -> MapData {
+    let volumes = map_volumes(yaixm, settings).map_err(|e| e.to_string());
+    let compare = compare.map_or(&[][..], |c| &c.polygons);
+
+    let points = volumes
+        .iter()
+        .flatten()
+        .flat_map(|v| v.points.iter())
+        .chain(compare.iter().flatten());
+    let (mut lat_min, mut lat_max, mut lon_min, mut lon_max) = (90.0, -90.0, 180.0, -180.0);
+    for p in points {
+        lat_min = p.lat.min(lat_min);
+        lat_max = p.lat.max(lat_max);
+        lon_min = p.lon.min(lon_min);
+        lon_max = p.lon.max(lon_max);
+    }
+
+    // Default to the UK if there is nothing to show
+    if lat_min > lat_max {
+        (lat_min, lat_max, lon_min, lon_max) = (50.0, 59.0, -8.0, 2.0);
+    }
+
+    let lat0 = (lat_min + lat_max) / 2.0;
+    let proj = Projection {
+        lat0,
+        lon0: (lon_min + lon_max) / 2.0,
+        scale: lat0.to_radians().cos(),
+    };
+
+    let path = |points: &[LatLon]| {
+        let mut d = points
+            .iter()
+            .enumerate()
+            .map(|(n, p)| {
+                let (x, y) = proj.project(p);
+                format!("{}{:.2} {:.2}", if n == 0 { "M" } else { "L" }, x, y)
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        d.push('Z');
+        d
+    };
+
+    let paths = volumes.iter().flatten().map(|v| path(&v.points)).collect();
+    let compare = compare.iter().map(|p| path(p)).collect();
+    let region = settings
+        .region
+        .as_ref()
+        .map(|region| path(&region_polygon(region)));
+
+    // Add a margin around the data
+    let width = (lon_max - lon_min) * 60.0 * proj.scale;
+    let height = (lat_max - lat_min) * 60.0;
+    let margin = width.max(height) * 0.05 + 1.0;
+    let view = View {
+        x: -width / 2.0 - margin,
+        y: -height / 2.0 - margin,
+        width: width + 2.0 * margin,
+        height: height + 2.0 * margin,
+    };
+
+    MapData {
+        volumes,
+        paths,
+        compare,
+        region,
+        view,
+    }
+}
+
+#[function_component(MapTab)]
+pub fn map_tab(props: &Props) -> Html {
+    // OpenAir file for comparison
+    let compare = use_state(|| None::<Rc<Comparison>>);
+    let compare_error = use_state(|| None::<String>);
+
+    let data = {
+        let yaixm = props.yaixm.clone();
+        use_memo(
+            (props.active, props.settings.clone(), (*compare).clone()),
+            move |(active, settings, compare)| {
+                active.then(|| map_data(&yaixm, settings, compare.as_deref()))
+            },
+        )
+    };
+
+    // Current view, None to fit all volumes
+    let view = use_state(|| None::<View>);
+
+    // Volume under the mouse pointer, cleared when the volumes change
+    let hover = use_state(|| None::<usize>);
+    {
+        let hover = hover.clone();
+        use_effect_with(
+            (props.active, props.settings.clone(), (*compare).clone()),
+            move |_| {
+                hover.set(None);
+                || ()
+            },
+        );
+    }
+
+    // Drag start position and view
+    let drag: Rc<RefCell<Option<(i32, i32, View)>>> = use_mut_ref(|| None);
+
+    let svg_ref = use_node_ref();
+
+    let Some(data) = &*data else {
+        return html! {};
+    };
+    let current = view.unwrap_or(data.view);
+
+    let onmousedown = {
+        let drag = drag.clone();
+        Callback::from(move |e: MouseEvent| {
+            e.prevent_default();
+            *drag.borrow_mut() = Some((e.client_x(), e.client_y(), current));
+        })
+    };
+
+    let onmousemove = {
+        let drag = drag.clone();
+        let view = view.clone();
+        let svg_ref = svg_ref.clone();
+        Callback::from(move |e: MouseEvent| {
+            let Some((x0, y0, start)) = *drag.borrow() else {
+                return;
+            };
+            let Some(svg) = svg_ref.cast::<Element>() else {
+                return;
+            };
+
+            // View units per screen pixel (SVG is scaled to fit)
+            let scale = (start.width / svg.client_width().max(1) as f64)
+                .max(start.height / svg.client_height().max(1) as f64);
+
+            view.set(Some(View {
+                x: start.x - (e.client_x() - x0) as f64 * scale,
+                y: start.y - (e.client_y() - y0) as f64 * scale,
+                ..start
+            }));
+        })
+    };
+
+    let onmouseup = {
+        let drag = drag.clone();
+        Callback::from(move |_: MouseEvent| {
+            *drag.borrow_mut() = None;
+        })
+    };
+
+    let onwheel = {
+        let view = view.clone();
+        Callback::from(move |e: WheelEvent| {
+            e.prevent_default();
+            let factor = if e.delta_y() < 0.0 {
+                ZOOM_STEP
+            } else {
+                1.0 / ZOOM_STEP
+            };
+            view.set(Some(current.zoom(factor)));
+        })
+    };
+
+    let onzoom = |factor: f64| {
+        let view = view.clone();
+        Callback::from(move |_: MouseEvent| view.set(Some(current.zoom(factor))))
+    };
+
+    let onreset = {
+        let view = view.clone();
+        Callback::from(move |_: MouseEvent| view.set(None))
+    };
+
+    let onupload = {
+        let compare = compare.clone();
+        let compare_error = compare_error.clone();
+        let view = view.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            input.set_value("");
+
+            let compare = compare.clone();
+            let compare_error = compare_error.clone();
+            let view = view.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let name = file.name();
+                let result = read_as_text(&Blob::from(file))
+                    .await
+                    .map_err(|err| err.to_string())
+                    .and_then(|text| read_comparison(name, &text));
+                match result {
+                    Ok(comparison) => {
+                        compare.set(Some(Rc::new(comparison)));
+                        compare_error.set(None);
+                        view.set(None);
+                    }
+                    Err(err) => compare_error.set(Some(err)),
+                }
+            });
+        })
+    };
+
+    let onclear_compare = {
+        let compare = compare.clone();
+        let compare_error = compare_error.clone();
+        Callback::from(move |_: MouseEvent| {
+            compare.set(None);
+            compare_error.set(None);
+        })
+    };
+
+    let volumes = match &data.volumes {
+        Ok(volumes) => volumes,
+        Err(err) => {
+            return html! {
+                <div class="notification is-danger">
+                  {"Unable to draw map: "}{ err }
+                </div>
+            }
+        }
+    };
+
+    let paths = volumes
+        .iter()
+        .zip(data.paths.iter())
+        .enumerate()
+        .map(|(n, (volume, d))| {
+            let onmouseenter = {
+                let hover = hover.clone();
+                Callback::from(move |_: MouseEvent| hover.set(Some(n)))
+            };
+            let onmouseleave = {
+                let hover = hover.clone();
+                Callback::from(move |_: MouseEvent| hover.set(None))
+            };
+
+            let colour = type_colour(volume.openair_type);
+            let width = if *hover == Some(n) { "3" } else { "1" };
+
+            html! {
+                <path d={d.clone()} fill={colour} fill-opacity="0.1" stroke={colour}
+                  stroke-width={width} vector-effect="non-scaling-stroke"
+                  {onmouseenter} {onmouseleave} />
+            }
+        })
+        .collect::<Html>();
+
+    let info = match hover.and_then(|n| volumes.get(n)) {
+        Some(volume) => html! {
+            <>
+            <strong>{ &volume.name }</strong>
+            {" "}{ volume.lower.to_string() }{" - "}{ volume.upper.to_string() }
+            if let Some(freq) = volume.frequency {
+              {" "}{ format!("{:.3} MHz", freq) }
+            }
+            </>
+        },
+        None => html! { {"Drag to pan, scroll to zoom"} },
+    };
+
+    let view_box = format!(
+        "{} {} {} {}",
+        current.x, current.y, current.width, current.height
+    );
+
+    html! {
+        <div class="box">
+          <div class="level">
+            <div class="level-left">
+              <div class="level-item">{ info }</div>
+            </div>
+            <div class="level-right">
+              <div class="level-item">
+                if let Some(comparison) = &*compare {
+                  <span class="tag is-medium">
+                    { format!("Comparing {}", comparison.name) }
+                    <button class="delete is-small" onclick={onclear_compare}></button>
+                  </span>
+                } else {
+                  <div class="file is-small">
+                    <label class="file-label">
+                      <input class="file-input" type="file" accept=".txt,.air,.openair" onchange={onupload} />
+                      <span class="file-cta">
+                        <span class="file-label">{"Compare OpenAir..."}</span>
+                      </span>
+                    </label>
+                  </div>
+                }
+              </div>
+              <div class="level-item buttons has-addons">
+                <button class="button is-small" onclick={onzoom(ZOOM_STEP)}>{"+"}</button>
+                <button class="button is-small" onclick={onzoom(1.0 / ZOOM_STEP)}>{"-"}</button>
+                <button class="button is-small" onclick={onreset}>{"Reset"}</button>
+              </div>
+            </div>
+          </div>
+          <svg ref={svg_ref} viewBox={view_box} width="100%" height="600"
+            style="cursor: move; background: #f8f8f8"
+            {onmousedown} {onmousemove} onmouseup={onmouseup.clone()} onmouseleave={onmouseup}
+            {onwheel}>
+            { paths }
+            { for data.compare.iter().map(|d| html! {
+                <path d={d.clone()} fill="none" stroke="#404040" stroke-width="1"
+                  stroke-dasharray="3 3" vector-effect="non-scaling-stroke" pointer-events="none" />
+            }) }
+            if let Some(d) = data.region.clone() {
+              <path {d} fill="none" stroke="#000000" stroke-width="1.5" stroke-dasharray="6 4"
+                vector-effect="non-scaling-stroke" pointer-events="none" />
+            }
+          </svg>
+          if let Some(err) = &*compare_error {
+            <p class="help is-danger">{"Unable to read OpenAir file: "}{ err }</p>
+          }
+        </div>
+    }
+}
@@ -13,28 +13,46 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 //
-use crate::state::{AirType, Format, Overlay, Settings};
-use crate::AirspaceSetting;
+use crate::components::level_field::LevelField;
+use crate::components::region_settings::RegionSettings;
+use crate::state::Action;
+use asselect::settings::{AirType, Format, Overlay, Settings};
+use asselect::yaixm::Level;
 use web_sys::HtmlInputElement;
-use yew::{function_component, html, Callback, Event, Html, Properties, TargetCast};
+use yew::{function_component, html, AttrValue, Callback, Event, Html, Properties, TargetCast};
 
 #[derive(Properties, PartialEq)]
 pub struct Props {
     pub settings: Settings,
-    pub callback: Callback<AirspaceSetting>,
+    pub callback: Callback<Action>,
 }
 
 #[function_component(OptionsTab)]
 pub fn options_tab(props: &Props) -> Html {
-    let onchange = props.callback.reform(|e: Event| {
-        let name = e.target_unchecked_into::<HtmlInputElement>().name();
-        let value = e.target_unchecked_into::<HtmlInputElement>().value();
-
-        AirspaceSetting { name, value }
-    });
+    // Convert selected value to settings action
+    let onchange = |action: fn(String) -> Action| {
+        props
+            .callback
+            .reform(move |e: Event| action(e.target_unchecked_into::<HtmlInputElement>().value()))
+    };
 
     let set = &props.settings;
 
+    let max_presets = vec![
+        (Level::Fl(660), AttrValue::Static("Unlimited")),
+        (Level::Fl(195), AttrValue::Static("FL195")),
+        (Level::Fl(125), AttrValue::Static("FL125")),
+        (Level::Fl(105), AttrValue::Static("FL105")),
+        (Level::Fl(65), AttrValue::Static("FL65")),
+    ];
+
+    let min_presets = vec![
+        (Level::Surface, AttrValue::Static("None")),
+        (Level::Altitude(3000), AttrValue::Static("3000 ft")),
+        (Level::Fl(50), AttrValue::Static("FL50")),
+        (Level::Fl(100), AttrValue::Static("FL100")),
+    ];
+
     html! {
         <div class="box">
           <div class="columns">
@@ -44,10 +62,15 @@ pub fn options_tab(props: &Props) -> Html {
                   {"Format"}
                   <div class="control">
                     <div class="select is-fullwidth">
-                      <select name="format" onchange={onchange.clone()}>
+                      <select name="format" onchange={onchange(|v| Action::SetFormat(Format::from_code(&v).unwrap_or(Format::OpenAir)))}>
                         <option value="openair" selected={set.format == Format::OpenAir}>{"OpenAir"}</option>
+                        <option value="openair_ext" selected={set.format == Format::OpenAirExtended}>{"OpenAir (extended)"}</option>
                         <option value="ratonly" selected={set.format == Format::RatOnly}>{"RA(T) only"}</option>
                         <option value="competition" selected={set.format == Format::Competition}>{"Competition"}</option>
+                        <option value="geojson" selected={set.format == Format::GeoJson}>{"GeoJSON"}</option>
+                        <option value="tnp" selected={set.format == Format::Tnp}>{"TNP"}</option>
+                        <option value="kml" selected={set.format == Format::Kml}>{"Google Earth (KML)"}</option>
+                        <option value="cub" selected={set.format == Format::Cub}>{"SeeYou (CUB)"}</option>
                       </select>
                     </div>
                   </div>
@@ -56,22 +79,11 @@ pub fn options_tab(props: &Props) -> Html {
             </div>
 
             <div class="column is-one-third">
-              <div class="field">
-                <label class="label">
-                  {"Maximum Level"}
-                  <div class="control">
-                    <div class="select is-fullwidth">
-                      <select name="max_level" onchange={onchange.clone()}>
-                        <option value="660" selected={set.max_level == 660}>{"Unlimited"}</option>
-                        <option value="195" selected={set.max_level == 195}>{"FL195"}</option>
-                        <option value="125" selected={set.max_level == 125}>{"FL125"}</option>
-                        <option value="105" selected={set.max_level == 105}>{"FL105"}</option>
-                        <option value="65" selected={set.max_level == 65}>{"FL65"}</option>
-                      </select>
-                    </div>
-                  </div>
-                </label>
-              </div>
+              <LevelField label="Maximum Level" level={set.max_level} presets={max_presets} callback={props.callback.reform(Action::SetMaxLevel)} />
+            </div>
+
+            <div class="column is-one-third">
+              <LevelField label="Minimum Level" level={set.min_level} presets={min_presets} callback={props.callback.reform(Action::SetMinLevel)} />
             </div>
           </div>
 
@@ -82,7 +94,7 @@ pub fn options_tab(props: &Props) -> Html {
                   {"HIRTA/GVS"}
                   <div class="control">
                     <div class="select is-fullwidth">
-                      <select name="hirta_gvs" onchange={onchange.clone()}>
+                      <select name="hirta_gvs" onchange={onchange(|v| Action::SetHirtaGvs(AirType::from_code(&v)))}>
                         <option value="exclude" selected={set.hirta_gvs.is_none()}>{"No"}</option>
                         <option value="danger" selected={set.hirta_gvs == Some(AirType::Danger)}>{"Danger"}</option>
                         <option value="restricted" selected={set.hirta_gvs == Some(AirType::Restricted)}>{"Restricted"}</option>
@@ -99,7 +111,7 @@ pub fn options_tab(props: &Props) -> Html {
                   {"Obstacle"}
                   <div class="control">
                     <div class="select is-fullwidth">
-                      <select name="obstacle" onchange={onchange.clone()}>
+                      <select name="obstacle" onchange={onchange(|v| Action::SetObstacle(AirType::from_code(&v)))}>
                         <option value="exclude" selected={set.obstacle.is_none()}>{"No"}</option>
                         <option value="danger" selected={set.obstacle == Some(AirType::Danger)}>{"Danger"}</option>
                         <option value="classf" selected={set.obstacle == Some(AirType::ClassF)}>{"Class F"}</option>
@@ -119,7 +131,7 @@ pub fn options_tab(props: &Props) -> Html {
                   {"Radio Frequency"}
                   <div class="control">
                     <div class="select is-fullwidth">
-                      <select name="radio" onchange={onchange.clone()}>
+                      <select name="radio" onchange={onchange(|v| Action::SetRadio(v == "yes"))}>
                         <option value="no" selected={!set.radio}>{"No"}</option>
                         <option value="yes" selected={set.radio}>{"Add to name"}</option>
                       </select>
@@ -135,7 +147,7 @@ pub fn options_tab(props: &Props) -> Html {
                   {"Altitude Overlay"}
                   <div class="control">
                     <div class="select is-fullwidth">
-                      <select name="overlay" onchange={onchange.clone()}>
+                      <select name="overlay" onchange={onchange(|v| Action::SetOverlay(Overlay::from_code(&v)))}>
                         <option value="no" selected={set.overlay.is_none()}>{"No"}</option>
                         <option value="fl195" selected={set.overlay == Some(Overlay::FL195)}>{"Bases to FL195"}</option>
                         <option value="fl105" selected={set.overlay == Some(Overlay::FL105)}>{"Bases to FL105"}</option>
@@ -146,7 +158,25 @@ pub fn options_tab(props: &Props) -> Html {
                 </label>
               </div>
             </div>
+
+            <div class="column is-one-third">
+              <div class="field">
+                <label class="label">
+                  {"Upper Limit"}
+                  <div class="control">
+                    <div class="select is-fullwidth">
+                      <select name="clip_upper" onchange={onchange(|v| Action::SetClipUpper(v == "yes"))}>
+                        <option value="no" selected={!set.clip_upper}>{"As published"}</option>
+                        <option value="yes" selected={set.clip_upper}>{"Clip to maximum level"}</option>
+                      </select>
+                    </div>
+                  </div>
+                </label>
+              </div>
+            </div>
           </div>
+
+          <RegionSettings region={set.region.clone()} clip={set.clip_region} callback={props.callback.clone()} />
         </div>
     }
 }
@@ -0,0 +1,146 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use crate::state::Action;
+use asselect::settings::Override;
+use asselect::yaixm::Yaixm;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use web_sys::HtmlInputElement;
+use yew::{
+    function_component, html, use_state, Callback, Event, Html, InputEvent, Properties, TargetCast,
+};
+
+// Maximum number of search results shown
+const MAX_RESULTS: usize = 50;
+
+#[derive(Properties)]
+pub struct Props {
+    pub yaixm: Rc<Yaixm>,
+    pub overrides: BTreeMap<String, Override>,
+    pub callback: Callback<Action>,
+}
+
+// YAIXM data is never modified once loaded, so compare by pointer
+impl PartialEq for Props {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.yaixm, &other.yaixm)
+            && self.overrides == other.overrides
+            && self.callback == other.callback
+    }
+}
+
+#[function_component(OverrideList)]
+pub fn override_list(props: &Props) -> Html {
+    let search = use_state(String::new);
+
+    let oninput = {
+        let search = search.clone();
+        Callback::from(move |e: InputEvent| {
+            search.set(e.target_unchecked_into::<HtmlInputElement>().value())
+        })
+    };
+
+    // With no search text list the current overrides, otherwise list
+    // features whose name or id match
+    let text = search.trim().to_lowercase();
+    let mut features = props
+        .yaixm
+        .airspace
+        .iter()
+        .filter(|f| {
+            if text.is_empty() {
+                props.overrides.contains_key(f.key())
+            } else {
+                f.name.to_lowercase().contains(&text)
+                    || f.id
+                        .as_ref()
+                        .is_some_and(|id| id.to_lowercase().contains(&text))
+            }
+        })
+        .collect::<Vec<_>>();
+    features.sort_by(|a, b| a.name.cmp(&b.name));
+    let more = features.len().saturating_sub(MAX_RESULTS);
+
+    let rows = features
+        .iter()
+        .take(MAX_RESULTS)
+        .map(|feature| {
+            let key = feature.key().to_string();
+            let value = props.overrides.get(&key).copied();
+            let onchange = props.callback.reform(move |e: Event| {
+                let value = match e.target_unchecked_into::<HtmlInputElement>().value().as_str() {
+                    "include" => Some(Override::Include),
+                    "exclude" => Some(Override::Exclude),
+                    _ => None,
+                };
+                Action::SetOverride {
+                    key: key.clone(),
+                    value,
+                }
+            });
+
+            html! {
+              <tr>
+                <td>
+                  { &feature.name }
+                  if let Some(id) = &feature.id {
+                    <span class="tag is-light ml-2">{ id }</span>
+                  }
+                </td>
+                <td>
+                  <div class="select is-small">
+                    <select {onchange}>
+                      <option value="default" selected={value.is_none()}>{"Default"}</option>
+                      <option value="include" selected={value == Some(Override::Include)}>{"Always include"}</option>
+                      <option value="exclude" selected={value == Some(Override::Exclude)}>{"Always exclude"}</option>
+                    </select>
+                  </div>
+                </td>
+              </tr>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <div>
+          <div class="field">
+            <div class="control">
+              <input class="input" type="search" placeholder="Search airspace names"
+                value={(*search).clone()} {oninput} />
+            </div>
+          </div>
+          if features.is_empty() {
+            <p class="has-text-grey">
+              if text.is_empty() {
+                {"No overrides set. Search for an airspace to include or exclude it
+                  regardless of the airspace type settings."}
+              } else {
+                {"No matching airspace"}
+              }
+            </p>
+          } else {
+            <table class="table is-narrow is-fullwidth">
+              <tbody>
+                { rows }
+              </tbody>
+            </table>
+            if more > 0 {
+              <p class="help">{ format!("{} more, refine the search to see them", more) }</p>
+            }
+          }
+        </div>
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use crate::ProfileAction;
+use web_sys::HtmlInputElement;
+use yew::{
+    function_component, html, use_state, AttrValue, Callback, Event, Html, InputEvent, Properties,
+    TargetCast,
+};
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub names: Vec<AttrValue>,
+    pub active: Option<AttrValue>,
+    pub modified: bool,
+    pub callback: Callback<ProfileAction>,
+}
+
+#[function_component(ProfileBar)]
+pub fn profile_bar(props: &Props) -> Html {
+    // New profile name
+    let name = use_state(String::new);
+
+    let oninput = {
+        let name = name.clone();
+        Callback::from(move |e: InputEvent| {
+            name.set(e.target_unchecked_into::<HtmlInputElement>().value());
+        })
+    };
+
+    let onchange = props.callback.reform(|e: Event| {
+        ProfileAction::Load(e.target_unchecked_into::<HtmlInputElement>().value())
+    });
+
+    // Save to new name if given, otherwise to the active profile
+    let new_name = name.trim().to_string();
+    let save_name = if new_name.is_empty() {
+        props.active.as_ref().map(|a| a.to_string())
+    } else {
+        Some(new_name.clone())
+    };
+
+    let onsave = {
+        let callback = props.callback.clone();
+        let name = name.clone();
+        let save_name = save_name.clone();
+        Callback::from(move |_| {
+            if let Some(save_name) = save_name.clone() {
+                callback.emit(ProfileAction::Save(save_name));
+                name.set(String::new());
+            }
+        })
+    };
+
+    let can_rename = props.active.is_some()
+        && !new_name.is_empty()
+        && !props.names.iter().any(|n| *n == new_name);
+
+    let onrename = {
+        let callback = props.callback.clone();
+        let name = name.clone();
+        let new_name = new_name.clone();
+        Callback::from(move |_| {
+            callback.emit(ProfileAction::Rename(new_name.clone()));
+            name.set(String::new());
+        })
+    };
+
+    let ondelete = props.callback.reform(|_| ProfileAction::Delete);
+
+    let options = props
+        .names
+        .iter()
+        .map(|n| {
+            html! {
+                <option value={n.clone()} selected={Some(n) == props.active.as_ref()}>{n}</option>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+      <div class="field has-addons">
+        <div class="control">
+          <div class="select">
+            <select {onchange}>
+              <option value="" selected={props.active.is_none()} disabled=true>{"Profile"}</option>
+              { options }
+            </select>
+          </div>
+        </div>
+        <div class="control">
+          <input class="input" type="text" placeholder="New name" value={(*name).clone()} {oninput} />
+        </div>
+        <div class="control">
+          <button class="button" disabled={save_name.is_none()} onclick={onsave}>{"Save"}</button>
+        </div>
+        <div class="control">
+          <button class="button" disabled={!can_rename} onclick={onrename}>{"Rename"}</button>
+        </div>
+        <div class="control">
+          <button class="button" disabled={props.active.is_none()} onclick={ondelete}>{"Delete"}</button>
+        </div>
+        if props.modified {
+          <p class="control">
+            <span class="button is-static">{"Modified"}</span>
+          </p>
+        }
+      </div>
+    }
+}
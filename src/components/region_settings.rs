@@ -0,0 +1,274 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use crate::state::Action;
+use asselect::convert::{can_clip, region_from_geojson};
+use asselect::settings::Region;
+use gloo::file::{futures::read_as_text, Blob};
+use web_sys::HtmlInputElement;
+use yew::{
+    function_component, html, use_effect_with, use_state, Callback, Event, Html, InputEvent,
+    Properties, TargetCast,
+};
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub region: Option<Region>,
+    pub clip: bool,
+    pub callback: Callback<Action>,
+}
+
+// Region type name and editable values
+fn region_fields(region: &Option<Region>) -> (&'static str, Vec<String>) {
+    match region {
+        Some(Region::Circle { lat, lon, radius }) => (
+            "circle",
+            vec![lat.to_string(), lon.to_string(), radius.to_string()],
+        ),
+        Some(Region::Bounds {
+            north,
+            south,
+            east,
+            west,
+        }) => (
+            "bounds",
+            vec![
+                north.to_string(),
+                south.to_string(),
+                east.to_string(),
+                west.to_string(),
+            ],
+        ),
+        Some(Region::Polygon { .. }) => ("polygon", Vec::new()),
+        None => ("none", Vec::new()),
+    }
+}
+
+// Region from type name and values
+fn parse_region(kind: &str, values: &[String]) -> Result<Region, String> {
+    let numbers = values
+        .iter()
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|_| "enter decimal numbers".to_string())?;
+
+    let region = match (kind, numbers.as_slice()) {
+        ("circle", [lat, lon, radius]) => Region::Circle {
+            lat: *lat,
+            lon: *lon,
+            radius: *radius,
+        },
+        ("bounds", [north, south, east, west]) => Region::Bounds {
+            north: *north,
+            south: *south,
+            east: *east,
+            west: *west,
+        },
+        _ => return Err("invalid region".to_string()),
+    };
+
+    if region.is_valid() {
+        Ok(region)
+    } else {
+        Err("invalid region".to_string())
+    }
+}
+
+#[function_component(RegionSettings)]
+pub fn region_settings(props: &Props) -> Html {
+    let (kind, values) = region_fields(&props.region);
+    let kind = use_state(|| kind.to_string());
+    let values = use_state(|| values);
+    let error = use_state(|| None::<String>);
+
+    // Reset fields when the region is changed elsewhere (undo, profile, etc.)
+    {
+        let kind = kind.clone();
+        let values = values.clone();
+        let error = error.clone();
+        use_effect_with(props.region.clone(), move |region| {
+            let (new_kind, new_values) = region_fields(region);
+            kind.set(new_kind.to_string());
+            values.set(new_values);
+            error.set(None);
+            || ()
+        });
+    }
+
+    let onkind = {
+        let kind = kind.clone();
+        let values = values.clone();
+        let callback = props.callback.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<HtmlInputElement>().value();
+            let n = match value.as_str() {
+                "circle" => 3,
+                "bounds" => 4,
+                _ => 0,
+            };
+            if value == "none" {
+                callback.emit(Action::SetRegion(None));
+            }
+            values.set(vec![String::new(); n]);
+            kind.set(value);
+        })
+    };
+
+    let oninput = |n: usize| {
+        let values = values.clone();
+        Callback::from(move |e: InputEvent| {
+            let mut new_values = (*values).clone();
+            new_values[n] = e.target_unchecked_into::<HtmlInputElement>().value();
+            values.set(new_values);
+        })
+    };
+
+    let onapply = {
+        let kind = kind.clone();
+        let values = values.clone();
+        let error = error.clone();
+        let callback = props.callback.clone();
+        Callback::from(move |_| match parse_region(&kind, &values) {
+            Ok(region) => {
+                error.set(None);
+                callback.emit(Action::SetRegion(Some(region)));
+            }
+            Err(err) => error.set(Some(err)),
+        })
+    };
+
+    let onupload = {
+        let error = error.clone();
+        let callback = props.callback.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            input.set_value("");
+
+            let error = error.clone();
+            let callback = callback.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let name = file.name();
+                let result = read_as_text(&Blob::from(file))
+                    .await
+                    .map_err(|err| err.to_string())
+                    .and_then(|text| region_from_geojson(&name, &text));
+                match result {
+                    Ok(region) => {
+                        error.set(None);
+                        callback.emit(Action::SetRegion(Some(region)));
+                    }
+                    Err(err) => error.set(Some(err)),
+                }
+            });
+        })
+    };
+
+    let onclip = props.callback.reform(|e: Event| {
+        Action::SetClipRegion(e.target_unchecked_into::<HtmlInputElement>().value() == "yes")
+    });
+
+    let labels: &[&str] = match kind.as_str() {
+        "circle" => &["Latitude", "Longitude", "Radius (nm)"],
+        "bounds" => &["North", "South", "East", "West"],
+        _ => &[],
+    };
+    let inputs = labels
+        .iter()
+        .zip(values.iter())
+        .enumerate()
+        .map(|(n, (label, value))| {
+            html! {
+                <div class="control">
+                  <input class="input" type="text" placeholder={*label} title={*label}
+                    value={value.clone()} oninput={oninput(n)} />
+                </div>
+            }
+        })
+        .collect::<Html>();
+
+    let polygon_name = match &props.region {
+        Some(Region::Polygon { name, .. }) => name.clone(),
+        _ => "No file".to_string(),
+    };
+    let convex = props.region.as_ref().is_none_or(can_clip);
+
+    html! {
+        <div class="columns">
+          <div class="column is-one-third">
+            <div class="field">
+              <label class="label">
+                {"Region"}
+                <div class="control">
+                  <div class="select is-fullwidth">
+                    <select onchange={onkind}>
+                      <option value="none" selected={*kind == "none"}>{"All"}</option>
+                      <option value="circle" selected={*kind == "circle"}>{"Centre and radius"}</option>
+                      <option value="bounds" selected={*kind == "bounds"}>{"Bounding box"}</option>
+                      <option value="polygon" selected={*kind == "polygon"}>{"Polygon (GeoJSON)"}</option>
+                    </select>
+                  </div>
+                </div>
+              </label>
+            </div>
+          </div>
+
+          <div class="column is-one-third">
+            <div class="field">
+              <label class="label">
+                {"Region Boundary"}
+                <div class="control">
+                  <div class="select is-fullwidth">
+                    <select onchange={onclip}>
+                      <option value="no" selected={!props.clip}>{"Remove airspace outside"}</option>
+                      <option value="yes" selected={props.clip}>{"Clip airspace at boundary"}</option>
+                    </select>
+                  </div>
+                </div>
+              </label>
+              if props.clip && !convex {
+                <p class="help">{"Clipping needs a convex region, airspace will not be clipped"}</p>
+              }
+            </div>
+          </div>
+
+          <div class="column">
+            if *kind == "polygon" {
+              <div class="file has-name mt-5">
+                <label class="file-label">
+                  <input class="file-input" type="file" accept=".json,.geojson" onchange={onupload} />
+                  <span class="file-cta">
+                    <span class="file-label">{"Upload..."}</span>
+                  </span>
+                  <span class="file-name">{ polygon_name }</span>
+                </label>
+              </div>
+            } else if *kind != "none" {
+              <div class="field has-addons mt-5">
+                { inputs }
+                <div class="control">
+                  <button class="button is-info" onclick={onapply}>{"Apply"}</button>
+                </div>
+              </div>
+            }
+            if let Some(err) = error.as_ref() {
+              <p class="help is-danger">{ err }</p>
+            }
+          </div>
+        </div>
+    }
+}
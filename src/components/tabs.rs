@@ -23,6 +23,9 @@ use yew::{
 pub struct Props {
     pub tab_names: Vec<AttrValue>,
     pub children: Children,
+    // Called with the name of the selected tab
+    #[prop_or_default]
+    pub onselect: Callback<AttrValue>,
 }
 
 #[function_component(Tabs)]
@@ -31,12 +34,21 @@ pub fn tabs(props: &Props) -> Html {
 
     let onclick = {
         let tab = tab.clone();
+        let onselect = props.onselect.clone();
+        let tab_names = props.tab_names.clone();
         Callback::from(move |e: MouseEvent| {
             let id: String = e
                 .target_unchecked_into::<HtmlElement>()
                 .id()
                 .parse()
                 .unwrap();
+            if let Some(name) = id
+                .strip_prefix("tab-")
+                .and_then(|n| n.parse::<usize>().ok())
+                .and_then(|n| tab_names.get(n))
+            {
+                onselect.emit(name.clone());
+            }
             tab.set(id);
         })
     };
@@ -13,13 +13,90 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 //
-use crate::state::{AirType, Format, Settings};
+use crate::settings::{AirType, Format, Override, Settings};
 use crate::yaixm::{
-    Arc, Boundary, Circle, Feature, IcaoClass, IcaoType, Loa, LocalType, Obstacle, Rule, Service,
-    Volume, Yaixm,
+    Arc, Boundary, Circle, Direction, Distance, Feature, IcaoClass, IcaoType, LatLon, Level, Loa,
+    LocalType, Obstacle, Rule, Service, Volume, Yaixm,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+mod cub;
+mod geojson;
+mod kml;
+mod map;
+mod region;
+mod tnp;
+
+pub use cub::cub;
+pub use geojson::geojson;
+pub use kml::kml;
+pub use map::{map_volumes, MapVolume};
+pub use region::{can_clip, region_from_geojson, region_polygon};
+pub use tnp::tnp;
+
+// Output options, independent of the airspace settings
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutputOptions {
+    // Production time for the file header, omitted if None
+    pub timestamp: Option<DateTime<Utc>>,
+    // User agent for the file header, omitted if None
+    pub user_agent: Option<String>,
+    // Sort features by name so output depends only on data and settings
+    pub reproducible: bool,
+}
+
+impl OutputOptions {
+    // Options for a file produced now
+    pub fn now(user_agent: &str) -> Self {
+        OutputOptions {
+            timestamp: Some(Utc::now()),
+            user_agent: Some(user_agent.to_string()),
+            reproducible: false,
+        }
+    }
+
+    // Options for reproducible output, without timestamp or user agent
+    pub fn reproducible() -> Self {
+        OutputOptions {
+            reproducible: true,
+            ..Default::default()
+        }
+    }
+}
+
+// Conversion error, identifying the feature, volume and field at fault
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConvertError {
+    pub feature: String,
+    pub volume: usize,
+    pub volume_id: Option<String>,
+    pub field: &'static str,
+    pub message: &'static str,
+}
+
+impl ConvertError {
+    fn new(feature: &Feature, n: usize, field: &'static str, message: &'static str) -> Self {
+        ConvertError {
+            feature: feature.name.clone(),
+            volume: n,
+            volume_id: feature.geometry[n].id.clone(),
+            field,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, volume {}", self.feature, self.volume + 1)?;
+        if let Some(id) = &self.volume_id {
+            write!(f, " ({})", id)?;
+        }
+        write!(f, ", {}: {}", self.field, self.message)
+    }
+}
 
 impl IcaoClass {
     fn as_str(&self) -> &'static str {
@@ -95,59 +172,127 @@ impl AirType {
     }
 }
 
-// Normalise all levels to flight level
-fn norm_level(value: &str) -> u16 {
-    if let Some(fl) = value.strip_prefix("FL") {
-        fl.parse().unwrap()
-    } else if value.ends_with(" ft") {
-        value.split(' ').next().unwrap().parse::<u16>().unwrap() / 100
-    } else {
-        0
+// All OpenAir types produced by volume_type()
+const OPENAIR_TYPES: [&str; 17] = [
+    "A", "B", "C", "D", "E", "F", "G", "CTA", "CTR", "MATZ", "OTHER", "P", "Q", "R", "RMZ", "TMZ",
+    "W",
+];
+
+// Display colour (#rrggbb) for OpenAir type
+pub fn type_colour(openair_type: &str) -> &'static str {
+    match openair_type {
+        "A" | "P" | "R" => "#ff0000",
+        "B" | "C" | "D" | "CTA" | "CTR" => "#0000ff",
+        "E" | "F" | "G" => "#00aa00",
+        "MATZ" => "#aa00aa",
+        "Q" => "#ff8000",
+        "W" => "#00ff00",
+        _ => "#808080",
     }
 }
 
-// Openair level format
-fn format_level(level: &str) -> String {
-    if let Some(alt) = level.strip_suffix(" ft") {
-        // Altitude
-        alt.to_string() + " ft"
-    } else {
-        // Flight level or SFC
-        level.to_string()
+// Pressure change (ft/hPa), standard pressure and range of QNH (hPa)
+// assumed when comparing altitudes with flight levels
+const FT_PER_HPA: f64 = 27.0;
+const QNH_STD: f64 = 1013.25;
+const QNH_MIN: f64 = 950.0;
+const QNH_MAX: f64 = 1050.0;
+
+// Highest UK terrain (ft), assumed when comparing heights with altitudes
+const MAX_TERRAIN: f64 = 4500.0;
+
+// True for levels relative to the ground
+fn is_agl(level: &Level) -> bool {
+    matches!(level, Level::Surface | Level::Height(_))
+}
+
+// Range of possible altitude (ft) for a level, allowing for the terrain
+// under heights
+fn altitude_range(level: &Level) -> (f64, f64) {
+    match level {
+        Level::Fl(fl) => (*fl as f64 * 100.0, *fl as f64 * 100.0),
+        Level::Surface => (0.0, MAX_TERRAIN),
+        Level::Altitude(alt) => (*alt as f64, *alt as f64),
+        Level::Height(hgt) => (*hgt as f64, *hgt as f64 + MAX_TERRAIN),
+    }
+}
+
+// Range of possible pressure altitude (ft) for a level
+fn pressure_altitude(level: &Level) -> (f64, f64) {
+    let (low, high) = altitude_range(level);
+    match level {
+        Level::Fl(_) => (low, high),
+        _ => (
+            low + (QNH_STD - QNH_MAX) * FT_PER_HPA,
+            high + (QNH_STD - QNH_MIN) * FT_PER_HPA,
+        ),
     }
 }
 
+// True if level a is at or above level b whatever the QNH and terrain
+fn level_at_or_above(a: &Level, b: &Level) -> bool {
+    match (a, b) {
+        (Level::Fl(a), Level::Fl(b)) => a >= b,
+        (Level::Fl(_), _) | (_, Level::Fl(_)) => pressure_altitude(a).0 >= pressure_altitude(b).1,
+        _ if is_agl(a) == is_agl(b) => altitude(a) >= altitude(b),
+        _ => altitude_range(a).0 >= altitude_range(b).1,
+    }
+}
+
+// True if level a is above level b whatever the QNH and terrain
+fn level_above(a: &Level, b: &Level) -> bool {
+    match (a, b) {
+        (Level::Fl(a), Level::Fl(b)) => a > b,
+        (Level::Fl(_), _) | (_, Level::Fl(_)) => pressure_altitude(a).0 > pressure_altitude(b).1,
+        _ if is_agl(a) == is_agl(b) => altitude(a) > altitude(b),
+        _ => altitude_range(a).0 > altitude_range(b).1,
+    }
+}
+
+// True if level a is below level b whatever the QNH and terrain
+fn level_below(a: &Level, b: &Level) -> bool {
+    level_above(b, a)
+}
+
+// True if a volume with this base is removed by the maximum level. Compared
+// at standard pressure, the same as the fixed maximum levels have always
+// been applied, so a 6500 ft base is removed at FL65
+fn above_max_level(lower: &Level, max_level: &Level) -> bool {
+    altitude(lower) >= altitude(max_level)
+}
+
 // Openair lat/lon format
-fn format_latlon(latlon: &str) -> String {
+fn format_latlon(latlon: &LatLon) -> String {
+    let (lat_d, lat_m, lat_s, ns) = latlon.lat_dms();
+    let (lon_d, lon_m, lon_s, ew) = latlon.lon_dms();
     format!(
-        "{}:{}:{} {} {}:{}:{} {}",
-        &latlon[..2],
-        &latlon[2..4],
-        &latlon[4..6],
-        &latlon[6..7],
-        &latlon[8..11],
-        &latlon[11..13],
-        &latlon[13..15],
-        &latlon[15..16]
+        "{:02}:{:02}:{:02} {} {:03}:{:02}:{:02} {}",
+        lat_d, lat_m, lat_s, ns, lon_d, lon_m, lon_s, ew
     )
 }
 
 // Openair distance format
-fn format_distance(distance: &str) -> String {
-    match distance.split_once(' ') {
-        Some((dist, unit)) => {
-            if unit == "km" {
-                format!("{:.3}", dist.parse::<f32>().unwrap() / 1.852)
-            } else {
-                dist.to_string()
-            }
-        }
-        _ => "".to_string(),
+fn format_distance(distance: &Distance) -> String {
+    match distance {
+        Distance::Nm(nm) => nm.to_string(),
+        Distance::Km(_) => format!("{:.3}", distance.nm()),
     }
 }
+
+// Level in metres, flight levels assume standard pressure
+fn altitude(level: &Level) -> f64 {
+    let feet = match level {
+        Level::Surface => 0.0,
+        Level::Altitude(alt) | Level::Height(alt) => *alt as f64,
+        Level::Fl(fl) => *fl as f64 * 100.0,
+    };
+
+    feet * 0.3048
+}
+
 // Remove unwanted feature/volume
 fn airfilter(feature: &Feature, vol: &Volume, settings: &Settings) -> bool {
-    let exclude = match feature.local_type {
+    let category_exclude = match feature.local_type {
         // No-ATZ
         Some(LocalType::NoAtz) => settings.unlicensed.is_none(),
         // Microlight
@@ -176,13 +321,29 @@ fn airfilter(feature: &Feature, vol: &Volume, settings: &Settings) -> bool {
         _ => false,
     };
 
-    !(exclude || (norm_level(&vol.lower) >= settings.max_level))
+    // Per-feature override takes precedence over type settings
+    let exclude = match settings.overrides.get(feature.key()) {
+        Some(Override::Include) => false,
+        Some(Override::Exclude) => true,
+        None => category_exclude,
+    };
+
+    // Volumes clipped away by the region have no boundary
+    !(exclude
+        || vol.boundary.is_empty()
+        || above_max_level(&vol.lower, &settings.max_level)
+        || level_below(&vol.upper, &settings.min_level))
 }
 
 // Give each volume a name
-fn do_name(feature: &Feature, vol: &Volume, n: usize, settings: &Settings) -> String {
-    let name = if let Some(name) = &vol.name {
-        name.clone()
+fn volume_name(
+    feature: &Feature,
+    vol: &Volume,
+    n: usize,
+    settings: &Settings,
+) -> Result<String, ConvertError> {
+    if let Some(name) = &vol.name {
+        Ok(name.clone())
     } else {
         let mut name = feature.name.clone();
 
@@ -215,10 +376,12 @@ fn do_name(feature: &Feature, vol: &Volume, n: usize, settings: &Settings) -> St
         if settings.format == Format::Competition && feature.geometry.len() > 1 {
             name.push('-');
             if let Some(seq) = &vol.seq {
-                name += &seq;
+                name += seq;
             } else {
                 let x = (b'A'..=b'Z').map(|c| c as char).nth(n);
-                name.push(x.unwrap());
+                name.push(x.ok_or_else(|| {
+                    ConvertError::new(feature, n, "seq", "too many volumes to sequence")
+                })?);
             }
         }
 
@@ -243,14 +406,21 @@ fn do_name(feature: &Feature, vol: &Volume, n: usize, settings: &Settings) -> St
             }
         };
 
-        name
-    };
+        Ok(name)
+    }
+}
 
-    format!("AN {}\n", name)
+fn do_name(
+    feature: &Feature,
+    vol: &Volume,
+    n: usize,
+    settings: &Settings,
+) -> Result<String, ConvertError> {
+    Ok(format!("AN {}\n", volume_name(feature, vol, n, settings)?))
 }
 
 // Give each volume a type
-fn do_type(feature: &Feature, volume: &Volume, settings: &Settings) -> String {
+fn volume_type(feature: &Feature, volume: &Volume, settings: &Settings) -> &'static str {
     let rules = feature
         .rules
         .iter()
@@ -260,7 +430,7 @@ fn do_type(feature: &Feature, volume: &Volume, settings: &Settings) -> String {
 
     let comp = settings.format == Format::Competition;
 
-    let openair_type = if rules.contains(&Rule::Notam) {
+    if rules.contains(&Rule::Notam) {
         // NOTAM activated airspace
         "G"
     } else {
@@ -328,32 +498,79 @@ fn do_type(feature: &Feature, volume: &Volume, settings: &Settings) -> String {
                 }
             }
         }
+    }
+}
+
+fn do_type(feature: &Feature, volume: &Volume, settings: &Settings) -> String {
+    format!("AC {}\n", volume_type(feature, volume, settings))
+}
+
+// Extended OpenAir class, from the (possibly user selected) OpenAir type
+// or the ICAO class
+fn do_class_ext(feature: &Feature, volume: &Volume, settings: &Settings) -> String {
+    let class = match volume_type(feature, volume, settings) {
+        c @ ("A" | "B" | "C" | "D" | "E" | "F" | "G") => c,
+        _ => volume
+            .icao_class
+            .or(feature.icao_class)
+            .map_or("UNCLASSIFIED", |c| c.as_str()),
+    };
+
+    format!("AC {}\n", class)
+}
+
+// Extended OpenAir airspace type, from the OpenAir type where that
+// identifies the type (so user type settings and TMZ/RMZ rules apply),
+// otherwise from the YAIXM type
+fn do_type_ext(feature: &Feature, volume: &Volume, settings: &Settings) -> String {
+    let airspace_type = match volume_type(feature, volume, settings) {
+        "CTA" => "CTA",
+        "CTR" => "CTR",
+        "P" => "PROHIBITED",
+        "Q" => "DANGER",
+        "R" => "RESTRICTED",
+        "W" => "GLIDING_SECTOR",
+        "MATZ" => "MATZ",
+        "RMZ" => "RMZ",
+        "TMZ" => "TMZ",
+        _ => match feature.icao_type {
+            IcaoType::Atz => "ATZ",
+            IcaoType::Awy => "AWY",
+            IcaoType::Cta => "CTA",
+            IcaoType::Ctr => "CTR",
+            IcaoType::D => "DANGER",
+            IcaoType::P => "PROHIBITED",
+            IcaoType::R => "RESTRICTED",
+            IcaoType::Tma => "TMA",
+            IcaoType::DOther => match feature.local_type {
+                Some(LocalType::Obstacle) => "UNCLASSIFIED",
+                _ => "DANGER",
+            },
+            IcaoType::Other => "UNCLASSIFIED",
+        },
     };
 
-    format!("AC {}\n", openair_type)
+    format!("AY {}\n", airspace_type)
+}
+
+fn do_station(callsign: &str) -> String {
+    format!("AG {}\n", callsign)
 }
 
 fn do_levels(volume: &Volume) -> String {
-    format!(
-        "AL {}\nAH {}\n",
-        format_level(&volume.lower),
-        format_level(&volume.upper)
-    )
+    format!("AL {}\nAH {}\n", volume.lower, volume.upper)
 }
 
 fn do_freq(freq: f64) -> String {
     format!("AF {:.3}\n", freq)
 }
 
-fn do_point(point: &str) -> String {
+fn do_point(point: &LatLon) -> String {
     format!("DP {}\n", format_latlon(point))
 }
 
-fn do_line(line: &[String]) -> String {
-    line.iter()
-        .map(|x| do_point(x))
-        .collect::<Vec<String>>()
-        .join("")
+fn do_line(line: &[LatLon]) -> String {
+    line.iter().map(do_point).collect::<Vec<String>>().join("")
 }
 
 fn do_circle(circle: &Circle) -> String {
@@ -364,8 +581,8 @@ fn do_circle(circle: &Circle) -> String {
     )
 }
 
-fn do_arc(arc: &Arc, from: &str) -> String {
-    let dir = if arc.dir == "cw" { "+" } else { "-" };
+fn do_arc(arc: &Arc, from: &LatLon) -> String {
+    let dir = if arc.dir == Direction::Cw { "+" } else { "-" };
 
     format!(
         "V D={}\nV X={}\nDB {}, {}\n",
@@ -376,60 +593,63 @@ fn do_arc(arc: &Arc, from: &str) -> String {
     )
 }
 
-fn do_boundary(boundary: &[Boundary]) -> String {
+fn do_boundary(boundary: &[Boundary]) -> Result<String, &'static str> {
     let mut out = String::new();
-    let mut prev = "";
+    let mut prev = None;
 
     for segment in boundary {
         match segment {
             Boundary::Line(line) => {
                 out.push_str(&do_line(line));
-                prev = line.last().unwrap();
+                prev = line.last();
             }
             Boundary::Arc(arc) => {
-                out.push_str(&do_arc(arc, prev));
-                prev = &arc.to;
+                out.push_str(&do_arc(arc, prev.ok_or("arc has no start point")?));
+                prev = Some(&arc.to);
             }
             Boundary::Circle(circle) => out.push_str(&do_circle(circle)),
         }
     }
 
     // Close the polygon
-    if let Boundary::Line(line) = &boundary[0] {
-        if line[0] != prev {
-            out.push_str(&do_point(&line[0]));
+    if let Boundary::Line(line) = boundary.first().ok_or("empty boundary")? {
+        let first = line.first().ok_or("empty line")?;
+        if Some(first) != prev {
+            out.push_str(&do_point(first));
         }
     }
 
-    out
+    Ok(out)
 }
 
 // Merge radio frequency data
 fn merge_services(airspace: &mut Vec<Feature>, services: &Vec<Service>) {
-    // Create frequency map
-    let mut frequencies = HashMap::new();
+    // Create service map
+    let mut service_map = HashMap::new();
     for service in services {
         for id in &service.controls {
-            frequencies.insert(id, service.frequency);
+            service_map.insert(id, service);
         }
     }
 
-    // Add frequency properties
+    // Add frequency and callsign properties
     for feature in airspace {
         for volume in &mut feature.geometry {
-            let volume_freq = if let Some(id) = &volume.id {
-                frequencies.get(&id)
+            let volume_service = if let Some(id) = &volume.id {
+                service_map.get(&id)
             } else {
                 None
             };
 
-            let feature_freq = if let Some(id) = &feature.id {
-                frequencies.get(&id)
+            let feature_service = if let Some(id) = &feature.id {
+                service_map.get(&id)
             } else {
                 None
             };
 
-            volume.frequency = volume_freq.or(feature_freq).cloned();
+            let service = volume_service.or(feature_service);
+            volume.frequency = service.map(|s| s.frequency);
+            volume.callsign = service.map(|s| s.callsign.clone());
         }
     }
 }
@@ -501,14 +721,15 @@ fn add_obstacles(airspace: &mut Vec<Feature>, obstacles: &Vec<Obstacle>) {
             local_type: Some(LocalType::Obstacle),
             rules: None,
             geometry: vec![Volume {
-                upper: obstacle.elevation.clone(),
-                lower: "SFC".to_string(),
+                upper: obstacle.elevation,
+                lower: Level::Surface,
                 boundary: vec![Boundary::Circle(Circle {
-                    centre: obstacle.position.clone(),
-                    radius: "0.5 nm".to_string(),
+                    centre: obstacle.position,
+                    radius: Distance::Nm(0.5),
                 })],
                 icao_class: None,
                 frequency: None,
+                callsign: None,
                 id: None,
                 name: None,
                 rules: None,
@@ -519,8 +740,23 @@ fn add_obstacles(airspace: &mut Vec<Feature>, obstacles: &Vec<Obstacle>) {
     }
 }
 
-// File header
-fn header(note: &str, airac: &str, commit: &str, user_agent: &str, settings: &Settings) -> String {
+// AIRAC date (YYYY-MM-DD) from release data
+pub fn airac_date(yaixm: &Yaixm) -> &str {
+    yaixm
+        .release
+        .airac_date
+        .get(..10)
+        .unwrap_or(&yaixm.release.airac_date)
+}
+
+// File header text
+fn header_text(
+    note: &str,
+    airac: &str,
+    commit: &str,
+    options: &OutputOptions,
+    settings: &Settings,
+) -> String {
     let mut hdr = "UK Airspace\n\
         Alan Sparrow (airspace@asselect.uk)\n\
         \n\
@@ -537,19 +773,51 @@ fn header(note: &str, airac: &str, commit: &str, user_agent: &str, settings: &Se
     .to_string();
 
     hdr.push_str(note);
-    hdr.push_str(&format!("\nAIRAC: {}\n", &airac[..10]));
+    hdr.push_str(&format!("\nAIRAC: {}\n", airac.get(..10).unwrap_or(airac)));
     hdr.push_str(&format!("Commit: {}\n", commit));
-    hdr.push_str(&format!("Produced: {}\n", Utc::now().to_rfc3339()));
-    hdr.push_str(&format!("User agent: {}\n", user_agent));
+    if let Some(timestamp) = options.timestamp {
+        hdr.push_str(&format!("Produced: {}\n", timestamp.to_rfc3339()));
+    }
+    if let Some(user_agent) = &options.user_agent {
+        hdr.push_str(&format!("User agent: {}\n", user_agent));
+    }
+    if let Some(region) = &settings.region {
+        hdr.push_str(&format!("Region: {}\n", region::describe(region)));
+        if settings.clip_region && region::can_clip(region) {
+            hdr.push_str("Airspace clipped to region\n");
+        }
+    }
+    if settings.clip_upper {
+        hdr.push_str(&format!("Upper limits clipped to {}\n", settings.max_level));
+    }
+
+    // Region is described above, so leave its points out of the dump
+    let settings = Settings {
+        region: None,
+        ..settings.clone()
+    };
     hdr.push_str(&textwrap::fill(format!("{:?}", settings).as_str(), 72));
+    hdr
+}
+
+// File header, with each line prefixed by comment marker
+fn header(
+    note: &str,
+    airac: &str,
+    commit: &str,
+    options: &OutputOptions,
+    settings: &Settings,
+    comment: &str,
+) -> String {
+    let hdr = header_text(note, airac, commit, options, settings);
 
-    // Prepend "*" to lines
+    // Prepend comment marker to lines
     hdr.split('\n')
         .map(|x| {
             if x.is_empty() {
-                "*".to_string()
+                comment.to_string()
             } else {
-                "* ".to_owned() + x
+                format!("{} {}", comment, x)
             }
         })
         .collect::<Vec<String>>()
@@ -557,8 +825,8 @@ fn header(note: &str, airac: &str, commit: &str, user_agent: &str, settings: &Se
         + "\n"
 }
 
-// Generate OpenAir data
-pub fn openair(yaixm: &Yaixm, settings: &Settings, user_agent: &str) -> String {
+// Build airspace list, with LOAs, obstacles, RA(T)s and frequencies merged
+fn build_airspace(yaixm: &Yaixm, settings: &Settings, options: &OutputOptions) -> Vec<Feature> {
     let mut airspace = yaixm.airspace.clone();
 
     if settings.format == Format::RatOnly {
@@ -596,28 +864,172 @@ pub fn openair(yaixm: &Yaixm, settings: &Settings, user_agent: &str) -> String {
         merge_services(&mut airspace, &yaixm.service);
     }
 
+    // Remove airspace outside region of interest
+    if let Some(region) = &settings.region {
+        region::filter_region(&mut airspace, region, settings.clip_region);
+    }
+
+    // Clip upper limits to maximum level
+    if settings.clip_upper {
+        clip_upper(&mut airspace, &settings.max_level);
+    }
+
+    // Stable sort, so features with the same name keep their data order
+    if options.reproducible {
+        airspace.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    airspace
+}
+
+// Limit volume upper levels to given flight level
+fn clip_upper(airspace: &mut [Feature], max_level: &Level) {
+    for volume in airspace.iter_mut().flat_map(|f| f.geometry.iter_mut()) {
+        // Volumes removed by the maximum level are left alone, rather than
+        // clipped upside down
+        if above_max_level(&volume.lower, max_level) {
+            continue;
+        }
+
+        // Clip unless the upper is at or below the maximum whatever the QNH
+        if !level_at_or_above(max_level, &volume.upper) {
+            volume.upper = *max_level;
+        }
+    }
+}
+
+// Generate OpenAir data
+pub fn openair(
+    yaixm: &Yaixm,
+    settings: &Settings,
+    options: &OutputOptions,
+) -> Result<String, ConvertError> {
+    let airspace = build_airspace(yaixm, settings, options);
+
     // Build OpenAir data
     let rel = &yaixm.release;
     let mut output = header(
         &rel.note,
         &rel.airac_date,
         &rel.commit,
-        user_agent,
+        options,
         settings,
+        "*",
     );
+    let extended = settings.format == Format::OpenAirExtended;
     for feature in airspace {
         for (n, volume) in feature.geometry.iter().enumerate() {
             if airfilter(&feature, volume, settings) {
                 output.push_str("*\n");
-                output.push_str(&do_type(&feature, volume, settings));
-                output.push_str(&do_name(&feature, volume, n, settings));
+                if extended {
+                    output.push_str(&do_class_ext(&feature, volume, settings));
+                    output.push_str(&do_type_ext(&feature, volume, settings));
+                } else {
+                    output.push_str(&do_type(&feature, volume, settings));
+                }
+                output.push_str(&do_name(&feature, volume, n, settings)?);
                 if let Some(freq) = volume.frequency {
                     output.push_str(&do_freq(freq));
                 }
+                if let (true, Some(callsign)) = (extended, &volume.callsign) {
+                    output.push_str(&do_station(callsign));
+                }
                 output.push_str(&do_levels(volume));
-                output.push_str(&do_boundary(&volume.boundary));
+                output.push_str(
+                    &do_boundary(&volume.boundary)
+                        .map_err(|e| ConvertError::new(&feature, n, "boundary", e))?,
+                );
             }
         }
     }
-    output
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agl_levels() {
+        // Height may be over high ground, so never certainly below an altitude
+        assert!(!level_below(&Level::Height(1500), &Level::Altitude(3000)));
+        assert!(!level_below(&Level::Height(1500), &Level::Fl(30)));
+        assert!(level_below(&Level::Height(1500), &Level::Altitude(7000)));
+        assert!(level_below(&Level::Height(1500), &Level::Height(2000)));
+        assert!(!level_below(&Level::Height(1500), &Level::Surface));
+
+        // but is never below the ground
+        assert!(level_at_or_above(
+            &Level::Height(3000),
+            &Level::Altitude(3000)
+        ));
+        assert!(!level_at_or_above(
+            &Level::Height(2000),
+            &Level::Altitude(3000)
+        ));
+        assert!(level_at_or_above(
+            &Level::Altitude(3000),
+            &Level::Altitude(3000)
+        ));
+        assert!(!level_at_or_above(&Level::Surface, &Level::Altitude(1000)));
+    }
+
+    #[test]
+    fn max_level_boundary() {
+        let yaixm: Yaixm = serde_json::from_str(include_str!("../tests/data/yaixm.json")).unwrap();
+        let feature = yaixm.airspace.iter().find(|f| f.name == "LONDON").unwrap();
+        let settings = Settings {
+            max_level: Level::Fl(65),
+            ..Settings::default()
+        };
+
+        let mut volume = feature.geometry[0].clone();
+        volume.upper = Level::Fl(100);
+
+        volume.lower = Level::Altitude(6500);
+        assert!(!airfilter(feature, &volume, &settings));
+
+        volume.lower = Level::Altitude(6400);
+        assert!(airfilter(feature, &volume, &settings));
+    }
+
+    #[test]
+    fn clip_upper_levels() {
+        let yaixm: Yaixm = serde_json::from_str(include_str!("../tests/data/yaixm.json")).unwrap();
+        let mut feature = yaixm
+            .airspace
+            .into_iter()
+            .find(|f| f.name == "LONDON")
+            .unwrap();
+        feature.geometry.truncate(1);
+
+        let clipped = |lower: Level, upper: Level| {
+            let mut airspace = [feature.clone()];
+            airspace[0].geometry[0].lower = lower;
+            airspace[0].geometry[0].upper = upper;
+            clip_upper(&mut airspace, &Level::Fl(65));
+            airspace[0].geometry[0].upper
+        };
+
+        // Altitude which may be above FL65, depending on QNH, is clipped
+        assert_eq!(
+            clipped(Level::Surface, Level::Altitude(7000)),
+            Level::Fl(65)
+        );
+        assert_eq!(clipped(Level::Surface, Level::Fl(100)), Level::Fl(65));
+
+        // Upper limits already below the maximum are unchanged
+        assert_eq!(
+            clipped(Level::Surface, Level::Altitude(4000)),
+            Level::Altitude(4000)
+        );
+        assert_eq!(clipped(Level::Surface, Level::Fl(55)), Level::Fl(55));
+
+        // Volumes based above the maximum aren't inverted
+        assert_eq!(clipped(Level::Fl(70), Level::Fl(100)), Level::Fl(100));
+        assert_eq!(
+            clipped(Level::Altitude(9000), Level::Fl(100)),
+            Level::Fl(100)
+        );
+    }
 }
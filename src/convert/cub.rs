@@ -0,0 +1,271 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use super::{
+    airac_date, airfilter, altitude, build_airspace, volume_name, volume_type, ConvertError,
+    OutputOptions,
+};
+use crate::geometry::{boundary_points, DEFAULT_CHORD_ERROR};
+use crate::settings::Settings;
+use crate::yaixm::{Feature, Level, Volume, Yaixm};
+
+// Naviter CUB file identifier
+const IDENT: u32 = 0x425a4143;
+
+// Record sizes
+const SIZE_OF_HEADER: i32 = 210;
+const SIZE_OF_ITEM: i32 = 43;
+const SIZE_OF_POINT: i32 = 5;
+
+// Point data flags
+const FLAG_ORIGIN: u8 = 0x81;
+const FLAG_POINT: u8 = 0x01;
+const FLAG_NAME: u8 = 0x40;
+
+// Airspace type (upper nibble of style)
+const TYPE_UNKNOWN: u8 = 0x00;
+const TYPE_CTR: u8 = 0x10;
+const TYPE_RESTRICTED: u8 = 0x20;
+const TYPE_PROHIBITED: u8 = 0x30;
+const TYPE_DANGER: u8 = 0x40;
+const TYPE_GLIDER: u8 = 0x50;
+const TYPE_MATZ: u8 = 0x60;
+const TYPE_TMZ: u8 = 0x70;
+const TYPE_RMZ: u8 = 0x80;
+
+// Altitude style
+const ALT_AGL: u8 = 1;
+const ALT_MSL: u8 = 2;
+const ALT_FL: u8 = 3;
+
+// Filtered volume, with boundary in radians (longitude, latitude)
+struct Item {
+    name: String,
+    style: u8,
+    alt_style: u8,
+    min_alt: i16,
+    max_alt: i16,
+    points: Vec<(f32, f32)>,
+}
+
+impl Item {
+    fn new(
+        feature: &Feature,
+        volume: &Volume,
+        n: usize,
+        settings: &Settings,
+    ) -> Result<Self, ConvertError> {
+        let (min_style, min_alt) = do_level(&volume.lower);
+        let (max_style, max_alt) = do_level(&volume.upper);
+
+        let mut points = boundary_points(&volume.boundary, DEFAULT_CHORD_ERROR)
+            .map_err(|e| ConvertError::new(feature, n, "boundary", e))?
+            .iter()
+            .map(|p| (p.lon.to_radians() as f32, p.lat.to_radians() as f32))
+            .collect::<Vec<(f32, f32)>>();
+
+        // CUB polygons are implicitly closed
+        points.pop();
+
+        Ok(Item {
+            name: volume_name(feature, volume, n, settings)?,
+            style: do_style(feature, volume, settings),
+            alt_style: min_style | (max_style << 4),
+            min_alt,
+            max_alt,
+            points,
+        })
+    }
+
+    // Bounding box (left, top, right, bottom)
+    fn bounds(&self) -> (f32, f32, f32, f32) {
+        self.points.iter().fold(
+            (f32::MAX, f32::MIN, f32::MIN, f32::MAX),
+            |(l, t, r, b), (lon, lat)| (l.min(*lon), t.max(*lat), r.max(*lon), b.min(*lat)),
+        )
+    }
+}
+
+// CUB style from OpenAir type (upper nibble) and class (lower nibble)
+fn do_style(feature: &Feature, volume: &Volume, settings: &Settings) -> u8 {
+    let class = |c: &str| c.as_bytes()[0] - b'A' + 1;
+
+    match volume_type(feature, volume, settings) {
+        c @ ("A" | "B" | "C" | "D" | "E" | "F" | "G") => TYPE_UNKNOWN | class(c),
+        "CTA" | "CTR" => {
+            TYPE_CTR
+                | volume
+                    .icao_class
+                    .or(feature.icao_class)
+                    .map_or(0, |c| class(c.as_str()))
+        }
+        "P" => TYPE_PROHIBITED,
+        "Q" => TYPE_DANGER,
+        "R" => TYPE_RESTRICTED,
+        "W" => TYPE_GLIDER,
+        "MATZ" => TYPE_MATZ,
+        "TMZ" => TYPE_TMZ,
+        "RMZ" => TYPE_RMZ,
+        _ => TYPE_UNKNOWN,
+    }
+}
+
+// Altitude style and level in metres
+fn do_level(level: &Level) -> (u8, i16) {
+    let style = match level {
+        Level::Fl(_) => ALT_FL,
+        Level::Altitude(_) => ALT_MSL,
+        Level::Surface | Level::Height(_) => ALT_AGL,
+    };
+
+    (style, altitude(level).round() as i16)
+}
+
+fn put_u8(buf: &mut Vec<u8>, x: u8) {
+    buf.push(x);
+}
+
+fn put_i16(buf: &mut Vec<u8>, x: i16) {
+    buf.extend_from_slice(&x.to_le_bytes());
+}
+
+fn put_i32(buf: &mut Vec<u8>, x: i32) {
+    buf.extend_from_slice(&x.to_le_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, x: u32) {
+    buf.extend_from_slice(&x.to_le_bytes());
+}
+
+fn put_f32(buf: &mut Vec<u8>, x: f32) {
+    buf.extend_from_slice(&x.to_le_bytes());
+}
+
+// Fixed length, zero padded string
+fn put_str(buf: &mut Vec<u8>, s: &str, len: usize) {
+    let mut bytes = s.bytes().take(len - 1).collect::<Vec<u8>>();
+    bytes.resize(len, 0);
+    buf.extend_from_slice(&bytes);
+}
+
+// Encode point data, offsets relative to item's bottom left corner
+fn do_points(item: &Item, scale: f32) -> Vec<u8> {
+    let (left, _, _, bottom) = item.bounds();
+    let mut buf = Vec::new();
+
+    put_u8(&mut buf, FLAG_ORIGIN);
+    put_i16(&mut buf, 0);
+    put_i16(&mut buf, 0);
+
+    for (lon, lat) in &item.points {
+        put_u8(&mut buf, FLAG_POINT);
+        put_i16(&mut buf, ((lon - left) / scale).round() as i16);
+        put_i16(&mut buf, ((lat - bottom) / scale).round() as i16);
+    }
+
+    // Name attribute
+    let name = item
+        .name
+        .bytes()
+        .take(u8::MAX as usize)
+        .collect::<Vec<u8>>();
+    put_u8(&mut buf, FLAG_NAME);
+    put_u8(&mut buf, name.len() as u8);
+    buf.extend_from_slice(&name);
+
+    buf
+}
+
+// Generate CUB data
+pub fn cub(
+    yaixm: &Yaixm,
+    settings: &Settings,
+    options: &OutputOptions,
+) -> Result<Vec<u8>, ConvertError> {
+    let airspace = build_airspace(yaixm, settings, options);
+
+    let mut items = Vec::new();
+    for feature in airspace {
+        for (n, volume) in feature.geometry.iter().enumerate() {
+            if airfilter(&feature, volume, settings) {
+                items.push(Item::new(&feature, volume, n, settings)?);
+            }
+        }
+    }
+
+    // Overall bounding box and maximum item size
+    let bounds = items.iter().map(Item::bounds).collect::<Vec<_>>();
+    let (left, top, right, bottom) = bounds.iter().fold(
+        (f32::MAX, f32::MIN, f32::MIN, f32::MAX),
+        |(l, t, r, b), x| (l.min(x.0), t.max(x.1), r.max(x.2), b.min(x.3)),
+    );
+    let max_width = bounds.iter().map(|x| x.2 - x.0).fold(0.0, f32::max);
+    let max_height = bounds.iter().map(|x| x.1 - x.3).fold(0.0, f32::max);
+    let max_points = items.iter().map(|x| x.points.len()).max().unwrap_or(0);
+
+    // Scale so point offsets fit in 16 bits
+    let scale = max_width.max(max_height).max(f32::EPSILON) / i16::MAX as f32;
+
+    // Header
+    let mut buf = Vec::new();
+    put_u32(&mut buf, IDENT);
+    put_str(
+        &mut buf,
+        &format!("UK Airspace, AIRAC {}", airac_date(yaixm)),
+        112,
+    );
+    buf.extend_from_slice(&[0; 16]); // Allowed serials
+    put_u8(&mut buf, 1); // PC byte order
+    put_u8(&mut buf, 0); // Not secured
+    put_u32(&mut buf, 0); // CRC32
+    buf.extend_from_slice(&[0; 16]); // Key
+    put_i32(&mut buf, SIZE_OF_ITEM);
+    put_i32(&mut buf, SIZE_OF_POINT);
+    put_i32(&mut buf, items.len() as i32);
+    put_i32(&mut buf, max_points as i32);
+    put_f32(&mut buf, left);
+    put_f32(&mut buf, top);
+    put_f32(&mut buf, right);
+    put_f32(&mut buf, bottom);
+    put_f32(&mut buf, max_width);
+    put_f32(&mut buf, max_height);
+    put_f32(&mut buf, scale);
+    put_i32(&mut buf, SIZE_OF_HEADER);
+    put_i32(&mut buf, SIZE_OF_HEADER + SIZE_OF_ITEM * items.len() as i32);
+    put_i32(&mut buf, 0); // Alignment
+
+    // Items and point data
+    let mut data = Vec::new();
+    for (item, (l, t, r, b)) in items.iter().zip(bounds) {
+        put_f32(&mut buf, l);
+        put_f32(&mut buf, t);
+        put_f32(&mut buf, r);
+        put_f32(&mut buf, b);
+        put_u8(&mut buf, item.style);
+        put_u8(&mut buf, item.alt_style);
+        put_i16(&mut buf, item.min_alt);
+        put_i16(&mut buf, item.max_alt);
+        put_i32(&mut buf, data.len() as i32);
+        put_i32(&mut buf, 0); // Time out
+        put_u32(&mut buf, 0); // Extra data
+        buf.extend_from_slice(&[0; 8]); // Active time
+        put_u8(&mut buf, 0); // Extended type
+
+        data.extend(do_points(item, scale));
+    }
+
+    buf.extend(data);
+    Ok(buf)
+}
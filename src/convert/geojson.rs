@@ -0,0 +1,100 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use super::{airfilter, build_airspace, volume_name, volume_type, ConvertError, OutputOptions};
+use crate::geometry::{boundary_points, DEFAULT_CHORD_ERROR};
+use crate::settings::Settings;
+use crate::yaixm::{Feature, LatLon, Rule, Volume, Yaixm};
+use serde_json::{json, Value};
+
+// GeoJSON position, rounded to approximately 0.1m
+fn position(point: &LatLon) -> Value {
+    let round = |x: f64| (x * 1e6).round() / 1e6;
+    json!([round(point.lon), round(point.lat)])
+}
+
+// Polygon with anticlockwise exterior ring (RFC 7946 right-hand rule)
+fn polygon(volume: &Volume) -> Result<Value, &'static str> {
+    let mut points = boundary_points(&volume.boundary, DEFAULT_CHORD_ERROR)?;
+
+    let area = points
+        .windows(2)
+        .map(|p| (p[1].lon - p[0].lon) * (p[1].lat + p[0].lat))
+        .sum::<f64>();
+    if area > 0.0 {
+        points.reverse();
+    }
+
+    Ok(json!({
+        "type": "Polygon",
+        "coordinates": [points.iter().map(position).collect::<Vec<Value>>()],
+    }))
+}
+
+fn properties(
+    feature: &Feature,
+    volume: &Volume,
+    n: usize,
+    settings: &Settings,
+) -> Result<Value, ConvertError> {
+    let rules = feature
+        .rules
+        .iter()
+        .chain(volume.rules.iter())
+        .flatten()
+        .map(Rule::as_str)
+        .collect::<Vec<&str>>();
+
+    Ok(json!({
+        "name": volume_name(feature, volume, n, settings)?,
+        "type": volume_type(feature, volume, settings),
+        "class": volume.icao_class.or(feature.icao_class).map(|c| c.as_str()),
+        "lower": volume.lower.to_string(),
+        "upper": volume.upper.to_string(),
+        "frequency": volume.frequency,
+        "rules": rules,
+    }))
+}
+
+// Generate GeoJSON data
+pub fn geojson(
+    yaixm: &Yaixm,
+    settings: &Settings,
+    options: &OutputOptions,
+) -> Result<String, ConvertError> {
+    let airspace = build_airspace(yaixm, settings, options);
+
+    let mut features = Vec::new();
+    for feature in airspace {
+        for (n, volume) in feature.geometry.iter().enumerate() {
+            if airfilter(&feature, volume, settings) {
+                let geometry =
+                    polygon(volume).map_err(|e| ConvertError::new(&feature, n, "boundary", e))?;
+
+                features.push(json!({
+                    "type": "Feature",
+                    "geometry": geometry,
+                    "properties": properties(&feature, volume, n, settings)?,
+                }));
+            }
+        }
+    }
+
+    Ok(json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+    .to_string())
+}
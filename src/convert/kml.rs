@@ -0,0 +1,160 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use super::{
+    airfilter, altitude, build_airspace, header_text, type_colour, volume_name, volume_type,
+    ConvertError, OutputOptions, OPENAIR_TYPES,
+};
+use crate::geometry::{boundary_points, DEFAULT_CHORD_ERROR};
+use crate::settings::Settings;
+use crate::yaixm::{Feature, Rule, Volume, Yaixm};
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn do_styles() -> String {
+    OPENAIR_TYPES
+        .iter()
+        .map(|name| {
+            // Convert #rrggbb to bbggrr
+            let rgb = type_colour(name);
+            let bgr = format!("{}{}{}", &rgb[5..7], &rgb[3..5], &rgb[1..3]);
+
+            format!(
+                "<Style id=\"{}\">\
+                 <LineStyle><color>ff{}</color><width>1.5</width></LineStyle>\
+                 <PolyStyle><color>40{}</color></PolyStyle>\
+                 </Style>\n",
+                name, bgr, bgr
+            )
+        })
+        .collect()
+}
+
+// Polygon from (latitude, longitude, altitude) points
+fn do_polygon(points: &[(f64, f64, f64)]) -> String {
+    let coords = points
+        .iter()
+        .map(|(lat, lon, alt)| format!("{:.6},{:.6},{:.0}", lon, lat, alt))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    format!(
+        "<Polygon><altitudeMode>absolute</altitudeMode>\
+         <outerBoundaryIs><LinearRing><coordinates>{}</coordinates></LinearRing></outerBoundaryIs>\
+         </Polygon>\n",
+        coords
+    )
+}
+
+// Volume as floor, ceiling and walls
+fn do_geometry(volume: &Volume) -> Result<String, &'static str> {
+    let points = boundary_points(&volume.boundary, DEFAULT_CHORD_ERROR)?;
+    let lower = altitude(&volume.lower);
+    let upper = altitude(&volume.upper);
+
+    let layer = |alt: f64| {
+        points
+            .iter()
+            .map(|p| (p.lat, p.lon, alt))
+            .collect::<Vec<(f64, f64, f64)>>()
+    };
+
+    let mut out = String::from("<MultiGeometry>\n");
+    out.push_str(&do_polygon(&layer(lower)));
+    out.push_str(&do_polygon(&layer(upper)));
+    for p in points.windows(2) {
+        out.push_str(&do_polygon(&[
+            (p[0].lat, p[0].lon, lower),
+            (p[1].lat, p[1].lon, lower),
+            (p[1].lat, p[1].lon, upper),
+            (p[0].lat, p[0].lon, upper),
+            (p[0].lat, p[0].lon, lower),
+        ]));
+    }
+    out.push_str("</MultiGeometry>\n");
+    Ok(out)
+}
+
+// Description balloon
+fn do_description(feature: &Feature, volume: &Volume) -> String {
+    let mut desc = format!("Lower: {}<br/>Upper: {}", volume.lower, volume.upper);
+
+    if let Some(freq) = volume.frequency {
+        desc.push_str(&format!("<br/>Frequency: {:.3}", freq));
+    }
+
+    let rules = feature
+        .rules
+        .iter()
+        .chain(volume.rules.iter())
+        .flatten()
+        .map(Rule::as_str)
+        .collect::<Vec<&str>>();
+    if !rules.is_empty() {
+        desc.push_str(&format!("<br/>Rules: {}", rules.join(", ")));
+    }
+
+    format!("<description>{}</description>\n", escape(&desc))
+}
+
+// Generate KML data
+pub fn kml(
+    yaixm: &Yaixm,
+    settings: &Settings,
+    options: &OutputOptions,
+) -> Result<String, ConvertError> {
+    let airspace = build_airspace(yaixm, settings, options);
+
+    let rel = &yaixm.release;
+    let hdr = header_text(&rel.note, &rel.airac_date, &rel.commit, options, settings);
+
+    let mut output = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <kml xmlns=\"http://www.opengis.net/kml/2.2\">\n\
+         <Document>\n\
+         <name>UK Airspace</name>\n",
+    );
+    output.push_str(&format!("<description>{}</description>\n", escape(&hdr)));
+    output.push_str(&do_styles());
+
+    for feature in airspace {
+        for (n, volume) in feature.geometry.iter().enumerate() {
+            if airfilter(&feature, volume, settings) {
+                output.push_str("<Placemark>\n");
+                output.push_str(&format!(
+                    "<name>{}</name>\n",
+                    escape(&volume_name(&feature, volume, n, settings)?)
+                ));
+                output.push_str(&do_description(&feature, volume));
+                output.push_str(&format!(
+                    "<styleUrl>#{}</styleUrl>\n",
+                    volume_type(&feature, volume, settings)
+                ));
+                output.push_str(
+                    &do_geometry(volume)
+                        .map_err(|e| ConvertError::new(&feature, n, "boundary", e))?,
+                );
+                output.push_str("</Placemark>\n");
+            }
+        }
+    }
+
+    output.push_str("</Document>\n</kml>\n");
+    Ok(output)
+}
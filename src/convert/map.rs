@@ -0,0 +1,59 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use super::{airfilter, build_airspace, volume_name, volume_type, ConvertError, OutputOptions};
+use crate::geometry::{boundary_points, DEFAULT_CHORD_ERROR};
+use crate::settings::Settings;
+use crate::yaixm::{LatLon, Level, Yaixm};
+
+// Chord error (nm) for map display
+const MAP_CHORD_ERROR: f64 = DEFAULT_CHORD_ERROR * 5.0;
+
+// Airspace volume for map display
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapVolume {
+    pub name: String,
+    pub openair_type: &'static str,
+    pub lower: Level,
+    pub upper: Level,
+    pub frequency: Option<f64>,
+    pub points: Vec<LatLon>,
+}
+
+// Generate list of filtered volumes
+pub fn map_volumes(yaixm: &Yaixm, settings: &Settings) -> Result<Vec<MapVolume>, ConvertError> {
+    let airspace = build_airspace(yaixm, settings, &OutputOptions::default());
+
+    let mut volumes = Vec::new();
+    for feature in airspace {
+        for (n, volume) in feature.geometry.iter().enumerate() {
+            if airfilter(&feature, volume, settings) {
+                let points = boundary_points(&volume.boundary, MAP_CHORD_ERROR)
+                    .map_err(|e| ConvertError::new(&feature, n, "boundary", e))?;
+
+                volumes.push(MapVolume {
+                    name: volume_name(&feature, volume, n, settings)?,
+                    openair_type: volume_type(&feature, volume, settings),
+                    lower: volume.lower,
+                    upper: volume.upper,
+                    frequency: volume.frequency,
+                    points,
+                });
+            }
+        }
+    }
+
+    Ok(volumes)
+}
@@ -0,0 +1,170 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use serde_json::Value;
+
+use crate::geometry::{
+    boundary_points, circle_points, clip_polygon, is_convex, point_in_polygon, polygons_intersect,
+    DEFAULT_CHORD_ERROR,
+};
+use crate::settings::Region;
+use crate::yaixm::{Boundary, Circle, Distance, Feature, LatLon};
+
+// Region as a closed polygon
+pub fn region_polygon(region: &Region) -> Vec<LatLon> {
+    let mut points = match region {
+        Region::Circle { lat, lon, radius } => circle_points(
+            &Circle {
+                centre: LatLon {
+                    lat: *lat,
+                    lon: *lon,
+                },
+                radius: Distance::Nm(*radius),
+            },
+            DEFAULT_CHORD_ERROR,
+        ),
+        Region::Bounds {
+            north,
+            south,
+            east,
+            west,
+        } => [
+            (*north, *west),
+            (*north, *east),
+            (*south, *east),
+            (*south, *west),
+        ]
+        .iter()
+        .map(|(lat, lon)| LatLon {
+            lat: *lat,
+            lon: *lon,
+        })
+        .collect(),
+        Region::Polygon { points, .. } => points
+            .iter()
+            .map(|(lat, lon)| LatLon {
+                lat: *lat,
+                lon: *lon,
+            })
+            .collect(),
+    };
+
+    if let Some(first) = points.first().copied() {
+        if points.last() != Some(&first) {
+            points.push(first);
+        }
+    }
+    points
+}
+
+// Clipping is only supported for convex regions
+pub fn can_clip(region: &Region) -> bool {
+    is_convex(&region_polygon(region))
+}
+
+// Region description for file header
+pub fn describe(region: &Region) -> String {
+    match region {
+        Region::Circle { lat, lon, radius } => {
+            format!("{} nm radius of {:.4}, {:.4}", radius, lat, lon)
+        }
+        Region::Bounds {
+            north,
+            south,
+            east,
+            west,
+        } => format!(
+            "N {:.4}, S {:.4}, E {:.4}, W {:.4}",
+            north, south, east, west
+        ),
+        Region::Polygon { name, points } => format!("{} ({} points)", name, points.len()),
+    }
+}
+
+// Remove features with no volumes inside the region, and optionally clip
+// volumes crossing the edge of a convex region
+pub fn filter_region(airspace: &mut Vec<Feature>, region: &Region, clip: bool) {
+    let polygon = region_polygon(region);
+    let clip = clip && is_convex(&polygon);
+
+    // Volumes with invalid boundaries are kept, and reported later
+    airspace.retain(|feature| {
+        feature.geometry.iter().any(|volume| {
+            boundary_points(&volume.boundary, DEFAULT_CHORD_ERROR)
+                .map_or(true, |points| polygons_intersect(&points, &polygon))
+        })
+    });
+
+    // Volumes clipped away are left with an empty boundary, and skipped by
+    // airfilter, so the remaining volumes keep their indices for naming
+    if clip {
+        for volume in airspace.iter_mut().flat_map(|f| f.geometry.iter_mut()) {
+            match boundary_points(&volume.boundary, DEFAULT_CHORD_ERROR) {
+                // Volumes wholly inside the region keep their boundary
+                Ok(points) if points.iter().all(|p| point_in_polygon(p, &polygon)) => (),
+                Ok(points) => {
+                    volume.boundary = match clip_polygon(&points, &polygon) {
+                        clipped if clipped.is_empty() => Vec::new(),
+                        clipped => vec![Boundary::Line(clipped)],
+                    }
+                }
+                Err(_) => (),
+            }
+        }
+        airspace.retain(|feature| feature.geometry.iter().any(|v| !v.boundary.is_empty()));
+    }
+}
+
+// Region from the first polygon in a GeoJSON geometry, feature or
+// feature collection
+pub fn region_from_geojson(name: &str, text: &str) -> Result<Region, String> {
+    let value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+
+    let ring = find_ring(&value).ok_or("no polygon found")?;
+    let points = ring
+        .iter()
+        .map(|position| match position.as_array().map(Vec::as_slice) {
+            Some([lon, lat, ..]) => match (lat.as_f64(), lon.as_f64()) {
+                (Some(lat), Some(lon)) => Ok((lat, lon)),
+                _ => Err("invalid position".to_string()),
+            },
+            _ => Err("invalid position".to_string()),
+        })
+        .collect::<Result<Vec<(f64, f64)>, String>>()?;
+
+    if points.len() < 3 {
+        return Err("polygon has too few points".to_string());
+    }
+
+    Ok(Region::Polygon {
+        name: name.to_string(),
+        points,
+    })
+}
+
+// Outer ring of first Polygon or MultiPolygon
+fn find_ring(value: &Value) -> Option<&Vec<Value>> {
+    match value.get("type")?.as_str()? {
+        "FeatureCollection" => value
+            .get("features")?
+            .as_array()?
+            .iter()
+            .find_map(find_ring),
+        "Feature" => find_ring(value.get("geometry")?),
+        "Polygon" => value.get("coordinates")?.get(0)?.as_array(),
+        "MultiPolygon" => value.get("coordinates")?.get(0)?.get(0)?.as_array(),
+        _ => None,
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use super::{
+    airfilter, build_airspace, header, volume_name, volume_type, ConvertError, OutputOptions,
+};
+use crate::settings::Settings;
+use crate::yaixm::{
+    Arc, Boundary, Circle, Direction, Feature, IcaoType, LatLon, Level, Volume, Yaixm,
+};
+
+// TNP lat/lon format
+fn format_latlon(latlon: &LatLon) -> String {
+    let (lat_d, lat_m, lat_s, ns) = latlon.lat_dms();
+    let (lon_d, lon_m, lon_s, ew) = latlon.lon_dms();
+    format!(
+        "{}{:02}{:02}{:02} {}{:03}{:02}{:02}",
+        ns, lat_d, lat_m, lat_s, ew, lon_d, lon_m, lon_s
+    )
+}
+
+// TNP level format
+fn format_level(level: &Level) -> String {
+    match level {
+        Level::Surface => "SFC".to_string(),
+        Level::Altitude(alt) => format!("{}ALT", alt),
+        Level::Height(hgt) => format!("{}AGL", hgt),
+        Level::Fl(fl) => format!("FL{}", fl),
+    }
+}
+
+// Map OpenAir type to TNP type and class
+fn do_type(feature: &Feature, volume: &Volume, settings: &Settings) -> String {
+    let openair_type = volume_type(feature, volume, settings);
+    let icao_class = volume
+        .icao_class
+        .or(feature.icao_class)
+        .map_or("X", |c| c.as_str());
+
+    let (tnp_type, tnp_class) = match openair_type {
+        "A" | "B" | "C" | "D" | "E" | "F" | "G" => {
+            if feature.icao_type == IcaoType::Awy {
+                ("AIRWAYS", openair_type)
+            } else {
+                ("CTA/CTR", openair_type)
+            }
+        }
+        "CTA" | "CTR" => ("CTA/CTR", icao_class),
+        "P" => ("PROHIBITED", "X"),
+        "Q" => ("DANGER", "X"),
+        "R" => ("RESTRICTED", "X"),
+        "W" => ("GSEC", "X"),
+        "MATZ" => ("MATZ", "X"),
+        "RMZ" => ("RMZ", icao_class),
+        "TMZ" => ("TMZ", icao_class),
+        _ => ("OTHER", "X"),
+    };
+
+    format!("TYPE={}\nCLASS={}\n", tnp_type, tnp_class)
+}
+
+fn do_name(
+    feature: &Feature,
+    volume: &Volume,
+    n: usize,
+    settings: &Settings,
+) -> Result<String, ConvertError> {
+    Ok(format!(
+        "TITLE={}\n",
+        volume_name(feature, volume, n, settings)?
+    ))
+}
+
+fn do_freq(freq: f64) -> String {
+    format!("RADIO={:.3}\n", freq)
+}
+
+fn do_levels(volume: &Volume) -> String {
+    format!(
+        "TOPS={}\nBASE={}\n",
+        format_level(&volume.upper),
+        format_level(&volume.lower)
+    )
+}
+
+fn do_point(point: &LatLon) -> String {
+    format!("POINT={}\n", format_latlon(point))
+}
+
+fn do_line(line: &[LatLon]) -> String {
+    line.iter().map(do_point).collect::<Vec<String>>().join("")
+}
+
+fn do_circle(circle: &Circle) -> String {
+    format!(
+        "CIRCLE RADIUS={:.2} CENTRE={}\n",
+        circle.radius.nm(),
+        format_latlon(&circle.centre)
+    )
+}
+
+fn do_arc(arc: &Arc) -> String {
+    let dir = if arc.dir == Direction::Cw {
+        "CLOCKWISE"
+    } else {
+        "ANTI-CLOCKWISE"
+    };
+
+    format!(
+        "{} RADIUS={:.2} CENTRE={} TO={}\n",
+        dir,
+        arc.radius.nm(),
+        format_latlon(&arc.centre),
+        format_latlon(&arc.to)
+    )
+}
+
+fn do_boundary(boundary: &[Boundary]) -> Result<String, &'static str> {
+    let mut out = String::new();
+    let mut prev = None;
+
+    for segment in boundary {
+        match segment {
+            Boundary::Line(line) => {
+                out.push_str(&do_line(line));
+                prev = line.last();
+            }
+            Boundary::Arc(arc) => {
+                prev.ok_or("arc has no start point")?;
+                out.push_str(&do_arc(arc));
+                prev = Some(&arc.to);
+            }
+            Boundary::Circle(circle) => out.push_str(&do_circle(circle)),
+        }
+    }
+
+    // Close the polygon
+    if let Boundary::Line(line) = boundary.first().ok_or("empty boundary")? {
+        let first = line.first().ok_or("empty line")?;
+        if Some(first) != prev {
+            out.push_str(&do_point(first));
+        }
+    }
+
+    Ok(out)
+}
+
+// Generate TNP data
+pub fn tnp(
+    yaixm: &Yaixm,
+    settings: &Settings,
+    options: &OutputOptions,
+) -> Result<String, ConvertError> {
+    let airspace = build_airspace(yaixm, settings, options);
+
+    let rel = &yaixm.release;
+    let mut output = header(
+        &rel.note,
+        &rel.airac_date,
+        &rel.commit,
+        options,
+        settings,
+        "#",
+    );
+    output.push_str("INCLUDE=YES\n");
+
+    for feature in airspace {
+        for (n, volume) in feature.geometry.iter().enumerate() {
+            if airfilter(&feature, volume, settings) {
+                output.push_str("#\n");
+                output.push_str(&do_type(&feature, volume, settings));
+                output.push_str(&do_name(&feature, volume, n, settings)?);
+                if let Some(freq) = volume.frequency {
+                    output.push_str(&do_freq(freq));
+                }
+                output.push_str(&do_levels(volume));
+                output.push_str(
+                    &do_boundary(&volume.boundary)
+                        .map_err(|e| ConvertError::new(&feature, n, "boundary", e))?,
+                );
+            }
+        }
+    }
+    Ok(output)
+}
@@ -0,0 +1,441 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use crate::yaixm::{Arc, Boundary, Circle, Direction, LatLon};
+use std::f64::consts::TAU;
+
+// Mean earth radius, nautical miles
+const EARTH_RADIUS: f64 = 3440.065;
+
+// Default maximum distance (nm) between an arc and its approximating chords
+pub const DEFAULT_CHORD_ERROR: f64 = 0.01;
+
+// Smallest chord error allowed, stops zero or negative errors giving a
+// single chord
+const MIN_CHORD_ERROR: f64 = 0.0001;
+
+// Initial bearing (radians) of great circle between two points
+pub fn bearing(from: &LatLon, to: &LatLon) -> f64 {
+    let (lat1, lon1) = (from.lat.to_radians(), from.lon.to_radians());
+    let (lat2, lon2) = (to.lat.to_radians(), to.lon.to_radians());
+    let dlon = lon2 - lon1;
+
+    f64::atan2(
+        dlon.sin() * lat2.cos(),
+        lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos(),
+    )
+}
+
+// Great circle distance (nm) between two points
+pub fn distance(from: &LatLon, to: &LatLon) -> f64 {
+    let (lat1, lon1) = (from.lat.to_radians(), from.lon.to_radians());
+    let (lat2, lon2) = (to.lat.to_radians(), to.lon.to_radians());
+
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+// Destination point given start point, bearing (radians) and distance (nm)
+pub fn destination(from: &LatLon, bearing: f64, dist: f64) -> LatLon {
+    let (lat1, lon1) = (from.lat.to_radians(), from.lon.to_radians());
+    let d = dist / EARTH_RADIUS;
+
+    let lat2 = (lat1.sin() * d.cos() + lat1.cos() * d.sin() * bearing.cos()).asin();
+    let lon2 = lon1
+        + f64::atan2(
+            bearing.sin() * d.sin() * lat1.cos(),
+            d.cos() - lat1.sin() * lat2.sin(),
+        );
+
+    LatLon {
+        lat: lat2.to_degrees(),
+        lon: lon2.to_degrees(),
+    }
+}
+
+// Number of chords needed to approximate a sweep (radians) within the
+// maximum chord error
+fn num_chords(sweep: f64, radius: f64, max_error: f64) -> usize {
+    let ratio = (1.0 - max_error.max(MIN_CHORD_ERROR) / radius).clamp(-1.0, 1.0);
+    let step = 2.0 * ratio.acos();
+
+    if step > 0.0 {
+        ((sweep.abs() / step).ceil() as usize).max(1)
+    } else {
+        1
+    }
+}
+
+// Approximate circle with (unclosed) polygon points
+pub fn circle_points(circle: &Circle, max_error: f64) -> Vec<LatLon> {
+    let radius = circle.radius.nm();
+
+    let n = num_chords(TAU, radius, max_error).max(3);
+    (0..n)
+        .map(|i| destination(&circle.centre, TAU * i as f64 / n as f64, radius))
+        .collect()
+}
+
+// Approximate arc with points, excluding the start point
+pub fn arc_points(arc: &Arc, from: &LatLon, max_error: f64) -> Vec<LatLon> {
+    let radius = arc.radius.nm();
+
+    let start = bearing(&arc.centre, from);
+    let end = bearing(&arc.centre, &arc.to);
+
+    // Angle swept by arc, positive clockwise
+    let sweep = if arc.dir == Direction::Cw {
+        (end - start).rem_euclid(TAU)
+    } else {
+        -(start - end).rem_euclid(TAU)
+    };
+
+    let n = num_chords(sweep, radius, max_error);
+    let mut points = (1..n)
+        .map(|i| destination(&arc.centre, start + sweep * i as f64 / n as f64, radius))
+        .collect::<Vec<LatLon>>();
+
+    points.push(arc.to);
+    points
+}
+
+// Convert boundary to closed polygon
+pub fn boundary_points(boundary: &[Boundary], max_error: f64) -> Result<Vec<LatLon>, &'static str> {
+    let mut points: Vec<LatLon> = Vec::new();
+
+    for segment in boundary {
+        match segment {
+            Boundary::Line(line) => points.extend(line),
+            Boundary::Arc(arc) => {
+                let prev = *points.last().ok_or("arc has no start point")?;
+                points.extend(arc_points(arc, &prev, max_error));
+            }
+            Boundary::Circle(circle) => points.extend(circle_points(circle, max_error)),
+        }
+    }
+
+    // Close the polygon
+    let first = *points.first().ok_or("empty boundary")?;
+    if Some(&first) != points.last() {
+        points.push(first);
+    }
+
+    Ok(points)
+}
+
+// The following treat latitude/longitude as planar coordinates, which is
+// adequate for regions of a few hundred miles away from the poles
+
+// Polygon without repeated closing point
+fn open_ring(polygon: &[LatLon]) -> &[LatLon] {
+    match polygon {
+        [first, .., last] if first == last => &polygon[..polygon.len() - 1],
+        _ => polygon,
+    }
+}
+
+// Twice the signed area, positive if anti-clockwise
+fn signed_area(polygon: &[LatLon]) -> f64 {
+    let ring = open_ring(polygon);
+    (0..ring.len())
+        .map(|i| {
+            let (a, b) = (&ring[i], &ring[(i + 1) % ring.len()]);
+            a.lon * b.lat - b.lon * a.lat
+        })
+        .sum()
+}
+
+// Cross product of (b - a) and (c - a)
+fn cross(a: &LatLon, b: &LatLon, c: &LatLon) -> f64 {
+    (b.lon - a.lon) * (c.lat - a.lat) - (b.lat - a.lat) * (c.lon - a.lon)
+}
+
+// Bounding box as (south, west, north, east)
+fn bounds(polygon: &[LatLon]) -> (f64, f64, f64, f64) {
+    polygon.iter().fold(
+        (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+        |(s, w, n, e), p| (s.min(p.lat), w.min(p.lon), n.max(p.lat), e.max(p.lon)),
+    )
+}
+
+fn segments_intersect(a1: &LatLon, a2: &LatLon, b1: &LatLon, b2: &LatLon) -> bool {
+    let d1 = cross(b1, b2, a1);
+    let d2 = cross(b1, b2, a2);
+    let d3 = cross(a1, a2, b1);
+    let d4 = cross(a1, a2, b2);
+
+    (d1 * d2 <= 0.0) && (d3 * d4 <= 0.0)
+}
+
+// Ray casting point in polygon test
+pub fn point_in_polygon(point: &LatLon, polygon: &[LatLon]) -> bool {
+    let ring = open_ring(polygon);
+    let mut inside = false;
+    for i in 0..ring.len() {
+        let (a, b) = (&ring[i], &ring[(i + 1) % ring.len()]);
+        if (a.lat > point.lat) != (b.lat > point.lat)
+            && point.lon < a.lon + (point.lat - a.lat) / (b.lat - a.lat) * (b.lon - a.lon)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+// True if polygons overlap, or one contains the other
+pub fn polygons_intersect(a: &[LatLon], b: &[LatLon]) -> bool {
+    let (a, b) = (open_ring(a), open_ring(b));
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+
+    let (s1, w1, n1, e1) = bounds(a);
+    let (s2, w2, n2, e2) = bounds(b);
+    if s1 > n2 || s2 > n1 || w1 > e2 || w2 > e1 {
+        return false;
+    }
+
+    let edges = |ring: &'_ [LatLon]| {
+        (0..ring.len())
+            .map(|i| (ring[i], ring[(i + 1) % ring.len()]))
+            .collect::<Vec<(LatLon, LatLon)>>()
+    };
+    let b_edges = edges(b);
+    let crossing = edges(a).iter().any(|(a1, a2)| {
+        b_edges
+            .iter()
+            .any(|(b1, b2)| segments_intersect(a1, a2, b1, b2))
+    });
+
+    crossing || point_in_polygon(&a[0], b) || point_in_polygon(&b[0], a)
+}
+
+// True if polygon is convex
+pub fn is_convex(polygon: &[LatLon]) -> bool {
+    let ring = open_ring(polygon);
+    let n = ring.len();
+    let crosses = (0..n)
+        .map(|i| cross(&ring[i], &ring[(i + 1) % n], &ring[(i + 2) % n]))
+        .filter(|c| *c != 0.0)
+        .collect::<Vec<f64>>();
+
+    n >= 3 && (crosses.iter().all(|c| *c > 0.0) || crosses.iter().all(|c| *c < 0.0))
+}
+
+// Sutherland-Hodgman clip of polygon by a convex clip polygon, returns a
+// closed polygon or an empty vector if nothing remains
+pub fn clip_polygon(subject: &[LatLon], clip: &[LatLon]) -> Vec<LatLon> {
+    let clip = open_ring(clip);
+    let orientation = signed_area(clip).signum();
+
+    let mut output = open_ring(subject).to_vec();
+    for i in 0..clip.len() {
+        let (c1, c2) = (&clip[i], &clip[(i + 1) % clip.len()]);
+        let inside = |p: &LatLon| cross(c1, c2, p) * orientation >= 0.0;
+        let intersection = |p: &LatLon, q: &LatLon| {
+            let (dp, dq) = (cross(c1, c2, p), cross(c1, c2, q));
+            let t = dp / (dp - dq);
+            LatLon {
+                lat: p.lat + t * (q.lat - p.lat),
+                lon: p.lon + t * (q.lon - p.lon),
+            }
+        };
+
+        let input = std::mem::take(&mut output);
+        for j in 0..input.len() {
+            let (prev, curr) = (&input[(j + input.len() - 1) % input.len()], &input[j]);
+            match (inside(prev), inside(curr)) {
+                (true, true) => output.push(*curr),
+                (true, false) => output.push(intersection(prev, curr)),
+                (false, true) => {
+                    output.push(intersection(prev, curr));
+                    output.push(*curr);
+                }
+                (false, false) => (),
+            }
+        }
+    }
+
+    // Remove repeated points, and anything left with no area
+    output.dedup_by(|a, b| (a.lat - b.lat).abs() < 1e-9 && (a.lon - b.lon).abs() < 1e-9);
+    if output.len() < 3 || signed_area(&output).abs() < 1e-9 {
+        return Vec::new();
+    }
+    output.push(output[0]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaixm::Distance;
+
+    fn latlon(lat: f64, lon: f64) -> LatLon {
+        LatLon { lat, lon }
+    }
+
+    fn square(south: f64, west: f64, north: f64, east: f64) -> Vec<LatLon> {
+        vec![
+            latlon(south, west),
+            latlon(south, east),
+            latlon(north, east),
+            latlon(north, west),
+            latlon(south, west),
+        ]
+    }
+
+    // Maximum distance between chords and the circle they approximate
+    fn max_sagitta(points: &[LatLon], radius: f64) -> f64 {
+        points
+            .windows(2)
+            .map(|w| {
+                let half = distance(&w[0], &w[1]) / 2.0;
+                radius - (radius * radius - half * half).sqrt()
+            })
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn chord_error_bounded() {
+        let centre = latlon(51.5, -1.0);
+        for radius in [0.5, 2.0, 10.0, 25.0] {
+            for max_error in [0.01, 0.001] {
+                let circle = Circle {
+                    centre,
+                    radius: Distance::Nm(radius),
+                };
+                let points = boundary_points(&[Boundary::Circle(circle)], max_error).unwrap();
+
+                for p in &points {
+                    assert!((distance(&centre, p) - radius).abs() < 1e-6);
+                }
+                let sagitta = max_sagitta(&points, radius);
+                assert!(sagitta <= max_error * 1.001, "{} {}", radius, sagitta);
+                assert!(sagitta > max_error / 4.0, "{} {}", radius, sagitta);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_chord_error() {
+        let circle = Circle {
+            centre: latlon(51.5, -1.0),
+            radius: Distance::Nm(1.0),
+        };
+
+        for max_error in [0.0, -1.0] {
+            let points = circle_points(&circle, max_error);
+            assert!(points.len() > 100);
+            assert!(max_sagitta(&points, 1.0) <= MIN_CHORD_ERROR * 1.001);
+        }
+    }
+
+    #[test]
+    fn arc_direction() {
+        let centre = latlon(51.5, -1.0);
+        let from = destination(&centre, 0.0, 5.0);
+        let to = destination(&centre, TAU / 4.0, 5.0);
+        let arc = |dir| Arc {
+            centre,
+            dir,
+            radius: Distance::Nm(5.0),
+            to,
+        };
+
+        // Clockwise, north to east
+        let cw = arc_points(&arc(Direction::Cw), &from, DEFAULT_CHORD_ERROR);
+        assert!(cw
+            .iter()
+            .map(|p| bearing(&centre, p))
+            .all(|b| (-1e-6..=TAU / 4.0 + 1e-6).contains(&b)));
+
+        // Anti-clockwise, north via west and south to east
+        let ccw = arc_points(&arc(Direction::Ccw), &from, DEFAULT_CHORD_ERROR);
+        assert!(ccw.iter().any(|p| p.lon < centre.lon));
+        assert!(ccw.iter().any(|p| p.lat < centre.lat));
+        assert!(ccw.len() > 2 * cw.len());
+    }
+
+    #[test]
+    fn arc_ends_on_to() {
+        let centre = latlon(51.5, -1.0);
+        let from = destination(&centre, 1.0, 3.0);
+        let to = latlon(51.45, -0.95);
+        for dir in [Direction::Cw, Direction::Ccw] {
+            let arc = Arc {
+                centre,
+                dir,
+                radius: Distance::Nm(3.0),
+                to,
+            };
+            assert_eq!(
+                arc_points(&arc, &from, DEFAULT_CHORD_ERROR).last(),
+                Some(&to)
+            );
+        }
+    }
+
+    #[test]
+    fn full_circle() {
+        let circle = Circle {
+            centre: latlon(51.5, -1.0),
+            radius: Distance::Nm(2.0),
+        };
+
+        let points = circle_points(&circle, DEFAULT_CHORD_ERROR);
+        assert!(points.len() >= 3);
+        assert_ne!(points.first(), points.last());
+
+        let closed = boundary_points(&[Boundary::Circle(circle)], DEFAULT_CHORD_ERROR).unwrap();
+        assert_eq!(closed.len(), points.len() + 1);
+        assert_eq!(closed.first(), closed.last());
+    }
+
+    #[test]
+    fn clip_square() {
+        let clip = square(0.0, 0.0, 2.0, 2.0);
+
+        // Overlapping corner
+        let clipped = clip_polygon(&square(1.0, 1.0, 3.0, 3.0), &clip);
+        assert_eq!(clipped.first(), clipped.last());
+        assert!((signed_area(&clipped).abs() - 2.0).abs() < 1e-9);
+        assert!(clipped
+            .iter()
+            .all(|p| (1.0..=2.0).contains(&p.lat) && (1.0..=2.0).contains(&p.lon)));
+
+        // Inside and outside
+        let inside = square(0.5, 0.5, 1.5, 1.5);
+        assert_eq!(clip_polygon(&inside, &clip).len(), inside.len());
+        assert!(clip_polygon(&square(3.0, 3.0, 4.0, 4.0), &clip).is_empty());
+
+        // Clockwise clip polygon
+        let reversed = clip.iter().rev().copied().collect::<Vec<LatLon>>();
+        let clipped = clip_polygon(&square(1.0, 1.0, 3.0, 3.0), &reversed);
+        assert!((signed_area(&clipped).abs() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn intersect_containment() {
+        let outer = square(0.0, 0.0, 4.0, 4.0);
+        let inner = square(1.0, 1.0, 2.0, 2.0);
+
+        assert!(polygons_intersect(&outer, &inner));
+        assert!(polygons_intersect(&inner, &outer));
+        assert!(polygons_intersect(&outer, &square(3.0, 3.0, 5.0, 5.0)));
+        assert!(!polygons_intersect(&inner, &square(3.0, 3.0, 5.0, 5.0)));
+        assert!(!polygons_intersect(&outer, &[]));
+    }
+}
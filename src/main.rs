@@ -13,39 +13,47 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 //
-use futures::try_join;
-use gloo::file::{Blob, ObjectUrl};
-use gloo::net::{http::Request, Error};
-use gloo::storage::{LocalStorage, Storage};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use futures::join;
+use gloo::events::EventListener;
+use gloo::file::{futures::read_as_text, Blob, ObjectUrl};
+use gloo::net::{
+    http::{Request, Response},
+    Error,
+};
+use gloo::storage::{errors::StorageError, LocalStorage, Storage};
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::{js_sys::Promise, JsFuture};
+use web_sys::{HtmlElement, HtmlInputElement, HtmlTextAreaElement, KeyboardEvent};
 use yew::{
-    classes, function_component, html, use_effect_with, use_node_ref, use_reducer, use_state,
-    AttrValue, Callback, Html,
+    classes, function_component, html, use_effect_with, use_memo, use_node_ref, use_reducer,
+    use_state, AttrValue, Callback, Event, Html, NodeRef, TargetCast, UseStateHandle,
 };
 
+use asselect::convert::{airac_date, cub, geojson, kml, openair, tnp, OutputOptions};
+use asselect::settings::{self, Settings};
+use asselect::yaixm::{gliding_sites, loa_names, rat_names, wave_names, Yaixm};
 use components::{
     about_tab::AboutTab, airspace_tab::AirspaceTab, extra_panel::ExtraPanel, extra_tab::ExtraTab,
-    notam_tab::NotamTab, options_tab::OptionsTab, tabs::Tabs,
+    map_tab::MapTab, notam_tab::NotamTab, options_tab::OptionsTab, override_list::OverrideList,
+    profile_bar::ProfileBar, tabs::Tabs,
 };
-use convert::openair;
 use state::{Action, State};
-use yaixm::{gliding_sites, loa_names, rat_names, wave_names, Yaixm};
 
 mod components;
-mod convert;
+mod share;
 mod state;
-mod yaixm;
 
 // Callback data structures
-pub struct AirspaceSetting {
-    pub name: String,
-    pub value: String,
-}
-
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub enum ExtraCategory {
     Rat,
     Loa,
     Wave,
+    Override,
 }
 
 pub struct ExtraSetting {
@@ -54,32 +62,102 @@ pub struct ExtraSetting {
     pub checked: bool,
 }
 
+pub enum ProfileAction {
+    Load(String),
+    Save(String),
+    Rename(String),
+    Delete,
+}
+
+// Settings and profiles from local storage, with any migration problems
+struct Stored {
+    settings: Settings,
+    profiles: BTreeMap<String, Settings>,
+    warnings: Vec<String>,
+}
+
+// Overlay data, None if not (yet) loaded, with any fetch errors
 #[derive(Default)]
 pub struct Overlay {
-    pub overlay_105: String,
-    pub overlay_195: String,
-    pub overlay_atzdz: String,
+    pub overlay_105: Option<String>,
+    pub overlay_195: Option<String>,
+    pub overlay_atzdz: Option<String>,
+    pub errors: Vec<String>,
+}
+
+// YAIXM data load state
+pub enum LoadState {
+    Loading,
+    Loaded(Rc<Yaixm>),
+    Failed(String),
+}
+
+#[wasm_bindgen]
+extern "C" {
+    // Asynchronous clipboard API (not yet stable in web-sys)
+    #[wasm_bindgen(catch, js_namespace = ["navigator", "clipboard"], js_name = writeText)]
+    fn clipboard_write_text(text: &str) -> Result<Promise, JsValue>;
 }
 
 #[function_component]
 fn App() -> Html {
     // Airspace data
-    let yaixm = use_state(|| None);
+    let yaixm = use_state(|| LoadState::Loading);
 
     // Overlay data
     let overlay = use_state(Overlay::default);
 
-    // User interface settings
-    let state = use_reducer(|| State {
-        settings: LocalStorage::get("settings").unwrap_or_default(),
+    // Settings from share link
+    let shared = use_state(location_settings);
+
+    // Settings and profiles from local storage
+    let stored = use_memo((), |_| load_stored());
+    let stored_warnings = use_state(|| stored.warnings.clone());
+
+    // User interface settings, share link takes precedence over local storage
+    let state = use_reducer(|| {
+        State::new(match &*shared {
+            Some(Ok(settings)) => settings.clone(),
+            _ => stored.settings.clone(),
+        })
     });
 
+    // Named settings profiles, and the active profile
+    let profiles = use_state(|| stored.profiles.clone());
+    let active_profile = use_state(|| LocalStorage::get::<String>("profile").ok());
+
+    // Settings import result, with any names dropped
+    let import_report = use_state(|| None::<Result<Vec<String>, String>>);
+
+    // Share link, and whether it was copied to the clipboard
+    let share_link = use_state(|| None::<(String, bool)>);
+
     // Release modal control
     let show_release = use_state(|| false);
 
+    // Conversion error message
+    let convert_error = use_state(|| None::<String>);
+
+    // Selected tab name, None for the initial (first) tab
+    let tab = use_state(|| None::<AttrValue>);
+
     // Reference for download anchor element
     let anchor_node_ref = use_node_ref();
 
+    // Undo/redo keyboard shortcuts
+    {
+        let dispatcher = state.dispatcher();
+        use_effect_with((), move |_| {
+            let listener = EventListener::new(&gloo::utils::document(), "keydown", move |e| {
+                if let Some(action) = e.dyn_ref::<KeyboardEvent>().and_then(history_action) {
+                    e.prevent_default();
+                    dispatcher.dispatch(action);
+                }
+            });
+            move || drop(listener)
+        });
+    }
+
     // Fetch YAIXM and overlay data
     {
         let yaixm = yaixm.clone();
@@ -87,33 +165,37 @@ fn App() -> Html {
 
         // use_effect_with((), ...) triggers only on first render of component
         use_effect_with((), move |_| {
-            wasm_bindgen_futures::spawn_local(async move {
-                // Get YAIXM data (and trigger page render)
-                yaixm.set(fetch_yaixm().await.ok());
-
-                // Get overlay data
-                let overlay_105 = fetch_overlay("overlay_105.txt");
-                let overlay_195 = fetch_overlay("overlay_195.txt");
-                let overlay_atzdz = fetch_overlay("overlay_atzdz.txt");
-
-                let response = try_join!(overlay_105, overlay_195, overlay_atzdz);
-                if let Ok((overlay_105, overlay_195, overlay_atzdz)) = response {
-                    overlay.set(Overlay {
-                        overlay_105,
-                        overlay_195,
-                        overlay_atzdz,
-                    });
-                };
-            });
+            clear_location_fragment();
+            load_data(yaixm, overlay);
             || ()
         });
     }
 
+    // Retry data fetch callback
+    let onretry = {
+        let yaixm = yaixm.clone();
+        let overlay = overlay.clone();
+        Callback::from(move |_| {
+            yaixm.set(LoadState::Loading);
+            load_data(yaixm.clone(), overlay.clone());
+        })
+    };
+
+    // Retry overlay fetch callback
+    let onretry_overlay = {
+        let overlay = overlay.clone();
+        Callback::from(move |_| {
+            wasm_bindgen_futures::spawn_local(load_overlays(overlay.clone()));
+        })
+    };
+
     // Save airspace callback
     let onsave = {
         let yaixm = yaixm.clone();
         let state = state.clone();
+        let overlay = overlay.clone();
         let anchor_node_ref = anchor_node_ref.clone();
+        let convert_error = convert_error.clone();
 
         let user_agent = web_sys::window()
             .and_then(|w| w.navigator().user_agent().ok())
@@ -123,27 +205,163 @@ fn App() -> Html {
             // Save settings in local storage
             let _ = LocalStorage::set("settings", &state.settings);
 
-            // Create OpenAir data
-            let oa = openair(yaixm.as_ref().unwrap(), &state.settings, &user_agent);
-            let overlay = match state.settings.overlay {
-                Some(state::Overlay::FL195) => overlay.overlay_195.as_str(),
-                Some(state::Overlay::FL105) => overlay.overlay_105.as_str(),
-                Some(state::Overlay::AtzDz) => overlay.overlay_atzdz.as_str(),
-                None => "",
+            let LoadState::Loaded(yaixm) = &*yaixm else {
+                return;
             };
+            let options = OutputOptions::now(&user_agent);
+            let result = match state.settings.format {
+                // Create CUB data
+                settings::Format::Cub => cub(yaixm, &state.settings, &options),
+                // Create GeoJSON data
+                settings::Format::GeoJson => {
+                    geojson(yaixm, &state.settings, &options).map(String::into_bytes)
+                }
+                // Create KML data
+                settings::Format::Kml => {
+                    kml(yaixm, &state.settings, &options).map(String::into_bytes)
+                }
+                // Create TNP data
+                settings::Format::Tnp => {
+                    tnp(yaixm, &state.settings, &options).map(String::into_bytes)
+                }
+                // Create OpenAir data, refusing to leave out a selected
+                // overlay that hasn't loaded
+                _ => {
+                    let overlay = match state.settings.overlay {
+                        Some(settings::Overlay::FL195) => overlay.overlay_195.as_deref(),
+                        Some(settings::Overlay::FL105) => overlay.overlay_105.as_deref(),
+                        Some(settings::Overlay::AtzDz) => overlay.overlay_atzdz.as_deref(),
+                        None => Some(""),
+                    };
+                    let Some(overlay) = overlay else {
+                        convert_error.set(Some(
+                            "altitude overlay not loaded, retry or select no overlay".to_string(),
+                        ));
+                        return;
+                    };
+                    openair(yaixm, &state.settings, &options).map(|oa| (oa + overlay).into_bytes())
+                }
+            };
+
+            match result {
+                Ok(data) => {
+                    convert_error.set(None);
+                    let filename = airspace_filename(yaixm, &state.settings.format);
+                    download(&anchor_node_ref, &data, &filename);
+                }
+                Err(err) => convert_error.set(Some(err.to_string())),
+            }
+        })
+    };
+
+    // Undo/redo callbacks
+    let onundo = {
+        let state = state.clone();
+        Callback::from(move |_| state.dispatch(Action::Undo))
+    };
 
-            let blob = Blob::new((oa + overlay).as_str());
-            let object_url = ObjectUrl::from(blob);
+    let onredo = {
+        let state = state.clone();
+        Callback::from(move |_| state.dispatch(Action::Redo))
+    };
 
-            // Trigger a "fake" download
-            let anchor_node_ref = anchor_node_ref.cast::<web_sys::HtmlAnchorElement>();
-            if let Some(anchor_node_ref) = anchor_node_ref {
-                anchor_node_ref.set_href(&object_url);
-                anchor_node_ref.click();
+    // Export settings callback
+    let onexport = {
+        let state = state.clone();
+        let anchor_node_ref = anchor_node_ref.clone();
+        Callback::from(move |_| {
+            if let Ok(json) = serde_json::to_string_pretty(&state.settings) {
+                download(&anchor_node_ref, json.as_bytes(), "asselect_settings.json");
             }
         })
     };
 
+    // Import settings callback
+    let onimport = {
+        let yaixm = yaixm.clone();
+        let state = state.clone();
+        let import_report = import_report.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            // Allow the same file to be selected again
+            input.set_value("");
+
+            let LoadState::Loaded(yaixm) = &*yaixm else {
+                return;
+            };
+            let yaixm = yaixm.clone();
+            let state = state.clone();
+            let import_report = import_report.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = read_as_text(&Blob::from(file))
+                    .await
+                    .map_err(|err| err.to_string())
+                    .and_then(|text| {
+                        serde_json::from_str::<Value>(&text).map_err(|err| err.to_string())
+                    })
+                    .map(|value| {
+                        let (mut settings, mut changes) = Settings::migrate(value);
+                        changes.extend(settings.retain_known(&yaixm));
+                        state.dispatch(Action::Load(Box::new(settings)));
+                        changes
+                    });
+                import_report.set(Some(result));
+            });
+        })
+    };
+
+    let onhide_import = {
+        let import_report = import_report.clone();
+        Callback::from(move |_| {
+            import_report.set(None);
+        })
+    };
+
+    // Share link callbacks
+    let onshare = {
+        let state = state.clone();
+        let share_link = share_link.clone();
+        Callback::from(move |_| {
+            let Some(link) = share_url(&state.settings) else {
+                return;
+            };
+
+            let share_link = share_link.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let copied = match clipboard_write_text(&link) {
+                    Ok(promise) => JsFuture::from(promise).await.is_ok(),
+                    Err(_) => false,
+                };
+                share_link.set(Some((link, copied)));
+            });
+        })
+    };
+
+    let onhide_share = {
+        let share_link = share_link.clone();
+        Callback::from(move |_| {
+            share_link.set(None);
+        })
+    };
+
+    let onhide_stored = {
+        let stored_warnings = stored_warnings.clone();
+        Callback::from(move |_| {
+            stored_warnings.set(Vec::new());
+        })
+    };
+
+    let onhide_shared = {
+        let shared = shared.clone();
+        Callback::from(move |_| {
+            shared.set(None);
+        })
+    };
+
     // Release modal callbacks
     let onshow_release = {
         let show_release = show_release.clone();
@@ -159,14 +377,65 @@ fn App() -> Html {
         })
     };
 
+    // Conversion error callback
+    let onhide_error = {
+        let convert_error = convert_error.clone();
+        Callback::from(move |_| {
+            convert_error.set(None);
+        })
+    };
+
     // General settings callback
     let onairspace_set = {
         let state = state.clone();
-        Callback::from(move |setting: AirspaceSetting| {
-            state.dispatch(Action::Set {
-                name: setting.name,
-                value: setting.value,
-            })
+        Callback::from(move |action: Action| state.dispatch(action))
+    };
+
+    // Settings profile callback
+    let onprofile = {
+        let state = state.clone();
+        let profiles = profiles.clone();
+        let active_profile = active_profile.clone();
+        Callback::from(move |action: ProfileAction| {
+            let mut new_profiles = (*profiles).clone();
+            let mut active = (*active_profile).clone();
+
+            match action {
+                ProfileAction::Load(name) => {
+                    if let Some(settings) = profiles.get(&name) {
+                        state.dispatch(Action::Load(Box::new(settings.clone())));
+                        active = Some(name);
+                    }
+                }
+                ProfileAction::Save(name) => {
+                    new_profiles.insert(name.clone(), state.settings.clone());
+                    active = Some(name);
+                }
+                ProfileAction::Rename(name) => {
+                    if let Some(settings) = active.and_then(|a| new_profiles.remove(&a)) {
+                        new_profiles.insert(name.clone(), settings);
+                    }
+                    active = Some(name);
+                }
+                ProfileAction::Delete => {
+                    if let Some(a) = active {
+                        new_profiles.remove(&a);
+                    }
+                    active = None;
+                }
+            }
+
+            // Save profiles in local storage
+            let _ = LocalStorage::set("profiles", &new_profiles);
+            match &active {
+                Some(name) => {
+                    let _ = LocalStorage::set("profile", name);
+                }
+                None => LocalStorage::delete("profile"),
+            }
+
+            profiles.set(new_profiles);
+            active_profile.set(active);
         })
     };
 
@@ -186,6 +455,7 @@ fn App() -> Html {
                 name: setting.name,
                 checked: setting.checked,
             }),
+            ExtraCategory::Override => (),
         })
     };
 
@@ -196,16 +466,16 @@ fn App() -> Html {
             ExtraCategory::Rat => state.dispatch(Action::ClearRat),
             ExtraCategory::Loa => state.dispatch(Action::ClearLoa),
             ExtraCategory::Wave => state.dispatch(Action::ClearWave),
+            ExtraCategory::Override => state.dispatch(Action::ClearOverrides),
         })
     };
 
     // HTML rendering
-    match yaixm.as_ref() {
+    match &*yaixm {
         // Render full interface if YAIXM data is available
-        Some(yaixm) => {
-            let airac_date = &yaixm.release.airac_date[..10];
+        LoadState::Loaded(yaixm) => {
+            let airac_date = airac_date(yaixm);
             let release_note = &yaixm.release.note;
-            let filename = format!("uk{}.txt", airac_date);
 
             let mut gliding_sites = gliding_sites(yaixm);
             gliding_sites.sort();
@@ -224,16 +494,32 @@ fn App() -> Html {
                 AttrValue::Static("Temporary Restrictions"),
                 AttrValue::Static("Local Agreements"),
                 AttrValue::Static("Wave Boxes"),
+                AttrValue::Static("Airspace Overrides"),
             ];
 
+            let profile_names = profiles
+                .keys()
+                .map(|n| AttrValue::from(n.clone()))
+                .collect::<Vec<AttrValue>>();
+            let profile_modified = active_profile
+                .as_ref()
+                .and_then(|a| profiles.get(a))
+                .is_some_and(|p| *p != state.settings);
+
             let tab_names = vec![
                 AttrValue::Static("Main"),
                 AttrValue::Static("Option"),
                 AttrValue::Static("Extra"),
+                AttrValue::Static("Map"),
                 AttrValue::Static("NOTAM"),
                 AttrValue::Static("About"),
             ];
 
+            let ontab = {
+                let tab = tab.clone();
+                Callback::from(move |name: AttrValue| tab.set(Some(name)))
+            };
+
             html! {
                 <>
                 <header class="hero is-small is-primary block">
@@ -247,14 +533,68 @@ fn App() -> Html {
                 </header>
 
                 <div class="container block">
-                  <Tabs {tab_names}>
+                  <div class="level mx-4">
+                    <div class="level-left">
+                      <div class="level-item">
+                        <ProfileBar names={profile_names} active={active_profile.as_ref().map(|a| AttrValue::from(a.clone()))} modified={profile_modified} callback={onprofile} />
+                      </div>
+                    </div>
+                    <div class="level-right">
+                      <div class="level-item buttons">
+                        <button class="button" title="Undo (Ctrl+Z)" disabled={!state.can_undo()} onclick={onundo}>{"Undo"}</button>
+                        <button class="button" title="Redo (Ctrl+Y)" disabled={!state.can_redo()} onclick={onredo}>{"Redo"}</button>
+                        <button class="button" onclick={onexport}>{"Export Settings"}</button>
+                        <div class="file">
+                          <label class="file-label">
+                            <input class="file-input" type="file" accept=".json,application/json" onchange={onimport} />
+                            <span class="file-cta">
+                              <span class="file-label">{"Import Settings"}</span>
+                            </span>
+                          </label>
+                        </div>
+                      </div>
+                    </div>
+                  </div>
+                </div>
+
+                if let Some(report) = import_report.as_ref() {
+                  <div class="container block">
+                    {
+                      match report {
+                        Ok(dropped) if dropped.is_empty() => html! {
+                          <div class="notification is-success mx-4">
+                            <button class="delete" onclick={onhide_import}></button>
+                            {"Settings imported"}
+                          </div>
+                        },
+                        Ok(dropped) => html! {
+                          <div class="notification is-warning mx-4">
+                            <button class="delete" onclick={onhide_import}></button>
+                            {"Settings imported with changes: "}{ dropped.join(", ") }
+                          </div>
+                        },
+                        Err(err) => html! {
+                          <div class="notification is-danger mx-4">
+                            <button class="delete" onclick={onhide_import}></button>
+                            {"Unable to import settings: "}{ err }
+                          </div>
+                        },
+                      }
+                    }
+                  </div>
+                }
+
+                <div class="container block">
+                  <Tabs {tab_names} onselect={ontab}>
                     <AirspaceTab settings={state.settings.clone()} {gliding_sites} callback={onairspace_set.clone()} />
                     <OptionsTab settings={state.settings.clone()} callback={onairspace_set.clone()} />
-                    <ExtraTab names={extra_names} categories={vec![ExtraCategory::Rat, ExtraCategory::Loa, ExtraCategory::Wave]} on_clear={onextra_clear.clone()}>
+                    <ExtraTab names={extra_names} categories={vec![ExtraCategory::Rat, ExtraCategory::Loa, ExtraCategory::Wave, ExtraCategory::Override]} on_clear={onextra_clear.clone()}>
                       <ExtraPanel category={ExtraCategory::Rat} names={rat_names} selected={rat_selected} callback={onextra_set.clone()}/>
                       <ExtraPanel category={ExtraCategory::Loa} names={loa_names} selected={loa_selected} callback={onextra_set.clone()}/>
                       <ExtraPanel category={ExtraCategory::Wave} names={wave_names} selected={wave_selected} callback={onextra_set.clone()}/>
+                      <OverrideList yaixm={yaixm.clone()} overrides={state.settings.overrides.clone()} callback={onairspace_set.clone()} />
                     </ExtraTab>
+                    <MapTab yaixm={yaixm.clone()} settings={state.settings.clone()} active={tab.as_deref() == Some("Map")} />
                     <NotamTab />
                     <AboutTab />
                   </Tabs>
@@ -265,12 +605,67 @@ fn App() -> Html {
                     <button class="button is-primary" onclick={onsave}>
                       {"Get Airspace"}
                     </button>
+                    <button class="button is-text ml-2" onclick={onshare}>
+                      {"Copy Share Link"}
+                    </button>
                     <a id="airac-button" class="button is-text is-pulled-right" onclick={onshow_release}>
                     {"AIRAC: "}{ airac_date }
                     </a>
                   </div>
                 </div>
 
+                if let Some((link, copied)) = share_link.as_ref() {
+                  <div class="container block">
+                    <div class="notification is-info mx-4">
+                      <button class="delete" onclick={onhide_share}></button>
+                      if *copied {
+                        {"Share link copied to clipboard: "}
+                      } else {
+                        {"Copy this share link: "}
+                      }
+                      <a href={link.clone()}>{ link }</a>
+                    </div>
+                  </div>
+                }
+
+                if !stored_warnings.is_empty() {
+                  <div class="container block">
+                    <div class="notification is-warning mx-4">
+                      <button class="delete" onclick={onhide_stored}></button>
+                      {"Some saved settings could not be restored: "}{ stored_warnings.join(", ") }
+                    </div>
+                  </div>
+                }
+
+                if let Some(Err(err)) = shared.as_ref() {
+                  <div class="container block">
+                    <div class="notification is-warning mx-4">
+                      <button class="delete" onclick={onhide_shared}></button>
+                      {"Share link ignored: "}{ err }
+                    </div>
+                  </div>
+                }
+
+                if !overlay.errors.is_empty() {
+                  <div class="container block">
+                    <div class="notification is-warning mx-4">
+                      {"Altitude overlay unavailable: "}{ overlay.errors.join(", ") }
+                      <button class="button is-small ml-2" onclick={onretry_overlay}>
+                        {"Retry"}
+                      </button>
+                    </div>
+                  </div>
+                }
+
+                if let Some(err) = convert_error.as_ref() {
+                  <div class="container block">
+                    <div class="notification is-danger mx-4">
+                      <button class="delete" onclick={onhide_error}></button>
+                      {"Unable to create airspace file: "}{ err }
+                    </div>
+                  </div>
+                }
+
                 <div class={classes!("modal", show_release.then(|| Some("is-active")))}>
                   <div class="modal-background"></div>
                   <div class="modal-content">
@@ -282,43 +677,224 @@ fn App() -> Html {
                   <button id="modal-close" class="modal-close is-large" onclick={onhide_release.clone()}></button>
                 </div>
 
-                <a ref={anchor_node_ref} id="download" hidden=true download={filename}></a>
+                <a ref={anchor_node_ref} id="download" hidden=true></a>
                 </>
             }
         }
 
-        None => {
+        LoadState::Loading => {
             html! {
                 { "Loading Airspace, please wait..." }
             }
         }
+
+        LoadState::Failed(err) => {
+            html! {
+                <div class="container block mt-4">
+                  <div class="notification is-danger mx-4">
+                    {"Unable to load airspace data: "}{ err }
+                  </div>
+                  <div class="mx-4">
+                    <button class="button is-primary" onclick={onretry}>
+                      {"Retry"}
+                    </button>
+                  </div>
+                </div>
+            }
+        }
+    }
+}
+
+// Undo/redo action for keyboard shortcut. Editable elements (text and
+// number inputs, text areas, contenteditable) are left alone so the
+// browser's own undo still works while typing
+fn history_action(e: &KeyboardEvent) -> Option<Action> {
+    if !(e.ctrl_key() || e.meta_key()) {
+        return None;
+    }
+    if let Some(target) = e.target() {
+        if let Some(input) = target.dyn_ref::<HtmlInputElement>() {
+            if !matches!(input.type_().as_str(), "checkbox" | "radio") {
+                return None;
+            }
+        }
+        if target.dyn_ref::<HtmlTextAreaElement>().is_some() {
+            return None;
+        }
+        if target
+            .dyn_ref::<HtmlElement>()
+            .is_some_and(HtmlElement::is_content_editable)
+        {
+            return None;
+        }
+    }
+
+    match e.key().to_lowercase().as_str() {
+        "z" if e.shift_key() => Some(Action::Redo),
+        "z" => Some(Action::Undo),
+        "y" => Some(Action::Redo),
+        _ => None,
+    }
+}
+
+// Airspace file name for the chosen format
+fn airspace_filename(yaixm: &Yaixm, format: &settings::Format) -> String {
+    let extension = match format {
+        settings::Format::GeoJson => "geojson",
+        settings::Format::Tnp => "sua",
+        settings::Format::Kml => "kml",
+        settings::Format::Cub => "cub",
+        _ => "txt",
+    };
+    format!("uk{}.{}", airac_date(yaixm), extension)
+}
+
+// Trigger a "fake" download
+fn download(anchor_node_ref: &NodeRef, data: &[u8], filename: &str) {
+    let blob = Blob::new(data);
+    let object_url = ObjectUrl::from(blob);
+
+    if let Some(anchor) = anchor_node_ref.cast::<web_sys::HtmlAnchorElement>() {
+        anchor.set_download(filename);
+        anchor.set_href(&object_url);
+        anchor.click();
+    }
+}
+
+// Get settings and profiles from local storage, upgrading old versions
+fn load_stored() -> Stored {
+    let mut warnings = Vec::new();
+
+    let settings = match LocalStorage::get::<Value>("settings") {
+        Ok(value) => {
+            let (settings, problems) = Settings::migrate(value);
+            warnings.extend(problems);
+            settings
+        }
+        Err(StorageError::KeyNotFound(_)) => Settings::default(),
+        Err(err) => {
+            warnings.push(err.to_string());
+            Settings::default()
+        }
+    };
+
+    let profiles = LocalStorage::get::<BTreeMap<String, Value>>("profiles")
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| {
+            let (settings, problems) = Settings::migrate(value);
+            warnings.extend(
+                problems
+                    .iter()
+                    .map(|p| format!("profile \"{}\" {}", name, p)),
+            );
+            (name, settings)
+        })
+        .collect();
+
+    Stored {
+        settings,
+        profiles,
+        warnings,
+    }
+}
+
+// Settings from share link in the URL fragment, if any
+fn location_settings() -> Option<Result<Settings, String>> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    let fragment = hash.strip_prefix('#')?;
+    (!fragment.is_empty()).then(|| share::decode(fragment))
+}
+
+// Remove share link from the URL so it isn't re-applied on reload
+fn clear_location_fragment() {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let location = window.location();
+    if let (Ok(history), Ok(path), Ok(search)) =
+        (window.history(), location.pathname(), location.search())
+    {
+        let _ = history.replace_state_with_url(&JsValue::NULL, "", Some(&(path + &search)));
     }
 }
 
+// Page URL with settings encoded in the fragment
+fn share_url(settings: &Settings) -> Option<String> {
+    let location = web_sys::window()?.location();
+    Some(format!(
+        "{}{}#{}",
+        location.origin().ok()?,
+        location.pathname().ok()?,
+        share::encode(settings)
+    ))
+}
+
+// Fetch YAIXM data (triggering page render) followed by overlay data
+fn load_data(yaixm: UseStateHandle<LoadState>, overlay: UseStateHandle<Overlay>) {
+    wasm_bindgen_futures::spawn_local(async move {
+        // Get YAIXM data
+        yaixm.set(match fetch_yaixm().await {
+            Ok(data) => LoadState::Loaded(Rc::new(data)),
+            Err(err) => LoadState::Failed(err.to_string()),
+        });
+
+        load_overlays(overlay).await;
+    });
+}
+
+// Fetch overlay data, recording any errors
+async fn load_overlays(overlay: UseStateHandle<Overlay>) {
+    let (overlay_105, overlay_195, overlay_atzdz) = join!(
+        fetch_overlay("overlay_105.txt"),
+        fetch_overlay("overlay_195.txt"),
+        fetch_overlay("overlay_atzdz.txt")
+    );
+
+    let mut errors = Vec::new();
+    let mut check = |path: &str, result: Result<String, Error>| {
+        result
+            .map_err(|err| errors.push(format!("{} ({})", path, err)))
+            .ok()
+    };
+
+    overlay.set(Overlay {
+        overlay_105: check("overlay_105.txt", overlay_105),
+        overlay_195: check("overlay_195.txt", overlay_195),
+        overlay_atzdz: check("overlay_atzdz.txt", overlay_atzdz),
+        errors,
+    });
+}
+
 // Get YAIXM data from server
 async fn fetch_yaixm() -> Result<Yaixm, Error> {
-    let result = Request::get("yaixm.json").send().await;
-    match result {
-        Ok(response) => response.json().await,
-        Err(e) => Err(e),
+    let response = Request::get("yaixm.json").send().await?;
+    if response.ok() {
+        response.json().await
+    } else {
+        Err(http_error(&response))
     }
 }
 
 // Get overlay data from server
 async fn fetch_overlay(path: &str) -> Result<String, Error> {
-    let result = Request::get(path).send().await;
-    match result {
-        Ok(response) => {
-            if response.ok() {
-                response.text().await
-            } else {
-                Ok("* Missing overlay".to_string())
-            }
-        }
-        Err(e) => Err(e),
+    let response = Request::get(path).send().await?;
+    if response.ok() {
+        response.text().await
+    } else {
+        Err(http_error(&response))
     }
 }
 
+// Error for unsuccessful HTTP response
+fn http_error(response: &Response) -> Error {
+    Error::GlooError(format!(
+        "HTTP {} {}",
+        response.status(),
+        response.status_text()
+    ))
+}
+
 fn main() {
     yew::Renderer::<App>::new().render();
 }
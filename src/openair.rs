@@ -0,0 +1,366 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+// OpenAir reader, producing YAIXM features. Each AC record starts a new
+// single volume feature. OpenAir types don't map one-to-one onto YAIXM
+// types, so the feature type is the closest match (AY records, where
+// present, are used in preference to AC)
+use std::fmt;
+
+use crate::geometry::distance;
+use crate::yaixm::{
+    Arc, Boundary, Circle, Direction, Distance, Feature, IcaoClass, IcaoType, LatLon, Level,
+    LocalType, Volume,
+};
+
+// Level used for unlimited upper limits
+const UNLIMITED: Level = Level::Fl(660);
+
+// Read error, with line number (from 1)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReadError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+// Airspace being read
+#[derive(Default)]
+struct Airspace {
+    line: usize,
+    class: String,
+    airspace_type: Option<String>,
+    name: Option<String>,
+    lower: Option<Level>,
+    upper: Option<Level>,
+    frequency: Option<f64>,
+    callsign: Option<String>,
+    boundary: Vec<Boundary>,
+    centre: Option<LatLon>,
+    dir: Option<Direction>,
+}
+
+impl Airspace {
+    fn last_point(&self) -> Option<&LatLon> {
+        match self.boundary.last()? {
+            Boundary::Line(line) => line.last(),
+            Boundary::Arc(arc) => Some(&arc.to),
+            Boundary::Circle(_) => None,
+        }
+    }
+
+    fn add_point(&mut self, point: LatLon) {
+        match self.boundary.last_mut() {
+            Some(Boundary::Line(line)) => line.push(point),
+            _ => self.boundary.push(Boundary::Line(vec![point])),
+        }
+    }
+
+    fn add_arc(&mut self, from: LatLon, to: LatLon) -> Result<(), String> {
+        let centre = self.centre.ok_or("arc without centre (V X=)")?;
+        if self.last_point() != Some(&from) {
+            self.add_point(from);
+        }
+        self.boundary.push(Boundary::Arc(Arc {
+            centre,
+            dir: self.dir.unwrap_or(Direction::Cw),
+            radius: Distance::Nm(distance(&centre, &to)),
+            to,
+        }));
+        Ok(())
+    }
+
+    fn add_circle(&mut self, radius: f64) -> Result<(), String> {
+        let centre = self.centre.ok_or("circle without centre (V X=)")?;
+        self.boundary.push(Boundary::Circle(Circle {
+            centre,
+            radius: Distance::Nm(radius),
+        }));
+        Ok(())
+    }
+
+    fn into_feature(mut self) -> Result<Feature, ReadError> {
+        let err = |message: &str| ReadError {
+            line: self.line,
+            message: message.to_string(),
+        };
+
+        let name = self
+            .name
+            .clone()
+            .ok_or_else(|| err("airspace without name (AN)"))?;
+        let lower = self
+            .lower
+            .ok_or_else(|| err("airspace without lower level (AL)"))?;
+        let upper = self
+            .upper
+            .ok_or_else(|| err("airspace without upper level (AH)"))?;
+        if self.boundary.is_empty() {
+            return Err(err("airspace without boundary"));
+        }
+
+        // Polygons are implicitly closed, so drop a repeated first point
+        let first = match self.boundary.first() {
+            Some(Boundary::Line(line)) => line.first().copied(),
+            _ => None,
+        };
+        if let Some(Boundary::Line(line)) = self.boundary.last_mut() {
+            if line.len() > 1 && line.last().copied() == first {
+                line.pop();
+            }
+        }
+
+        let (icao_type, local_type) = feature_type(&self.class, self.airspace_type.as_deref());
+        let icao_class = match self.airspace_type {
+            Some(_) => icao_class(&self.class),
+            None if icao_type == IcaoType::Cta => icao_class(&self.class),
+            None => None,
+        };
+
+        Ok(Feature {
+            id: None,
+            name,
+            icao_type,
+            local_type,
+            icao_class,
+            rules: None,
+            geometry: vec![Volume {
+                id: None,
+                name: None,
+                lower,
+                upper,
+                icao_class: None,
+                rules: None,
+                seq: None,
+                frequency: self.frequency,
+                callsign: self.callsign,
+                boundary: self.boundary,
+            }],
+        })
+    }
+}
+
+fn icao_class(class: &str) -> Option<IcaoClass> {
+    match class {
+        "A" => Some(IcaoClass::A),
+        "B" => Some(IcaoClass::B),
+        "C" => Some(IcaoClass::C),
+        "D" => Some(IcaoClass::D),
+        "E" => Some(IcaoClass::E),
+        "F" => Some(IcaoClass::F),
+        "G" => Some(IcaoClass::G),
+        _ => None,
+    }
+}
+
+// YAIXM type from OpenAir class (AC) and, if present, type (AY)
+fn feature_type(class: &str, airspace_type: Option<&str>) -> (IcaoType, Option<LocalType>) {
+    let type_name = airspace_type.unwrap_or(class);
+    match type_name {
+        "A" | "B" | "C" | "D" | "E" | "F" | "G" | "CTA" => (IcaoType::Cta, None),
+        "ATZ" => (IcaoType::Atz, None),
+        "AWY" => (IcaoType::Awy, None),
+        "CTR" => (IcaoType::Ctr, None),
+        "TMA" => (IcaoType::Tma, None),
+        "P" | "GP" | "PROHIBITED" => (IcaoType::P, None),
+        "R" | "RESTRICTED" => (IcaoType::R, None),
+        "Q" | "DANGER" => (IcaoType::D, None),
+        "W" | "GSEC" | "GLIDING_SECTOR" => (IcaoType::DOther, Some(LocalType::Glider)),
+        "MATZ" => (IcaoType::Other, Some(LocalType::Matz)),
+        "RMZ" => (IcaoType::Other, Some(LocalType::Rmz)),
+        "TMZ" => (IcaoType::Other, Some(LocalType::Tmz)),
+        _ => (IcaoType::Other, None),
+    }
+}
+
+// Parse level, e.g. "SFC", "GND", "2000 ft", "2000ft MSL", "1500 ft AGL",
+// "FL65", "FL 65" or "UNL"
+fn parse_level(value: &str) -> Result<Level, String> {
+    let err = || format!("invalid level \"{}\"", value);
+
+    let value = value.trim().to_uppercase();
+    if matches!(value.as_str(), "SFC" | "GND" | "MSL" | "0") {
+        return Ok(Level::Surface);
+    }
+    if matches!(value.as_str(), "UNL" | "UNLIM" | "UNLIMITED") {
+        return Ok(UNLIMITED);
+    }
+    if let Some(fl) = value.strip_prefix("FL") {
+        return fl.trim().parse().map(Level::Fl).map_err(|_| err());
+    }
+
+    let digits = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let feet = value[..digits].parse::<u32>().map_err(|_| err())?;
+    let unit = value[digits..].replace(' ', "");
+    match unit.as_str() {
+        "" | "F" | "FT" | "MSL" | "AMSL" | "ALT" | "FTMSL" | "FTAMSL" | "FTALT" => {
+            Ok(Level::Altitude(feet))
+        }
+        "AGL" | "SFC" | "GND" | "FTAGL" | "FTSFC" | "FTGND" => Ok(Level::Height(feet)),
+        _ => Err(err()),
+    }
+}
+
+// Parse one coordinate, e.g. "51:30:00", "51:30.5" or "051:30:00.5"
+fn parse_dms(value: &str) -> Option<f64> {
+    let mut parts = value.trim().split(':').map(|x| x.trim().parse::<f64>());
+    let d = parts.next()?.ok()?;
+    let m = parts.next().unwrap_or(Ok(0.0)).ok()?;
+    let s = parts.next().unwrap_or(Ok(0.0)).ok()?;
+    if parts.next().is_some() || m >= 60.0 || s >= 60.0 {
+        return None;
+    }
+    Some(d + m / 60.0 + s / 3600.0)
+}
+
+// Parse position, e.g. "51:30:00 N 001:15:00 W"
+fn parse_latlon(value: &str) -> Result<LatLon, String> {
+    let err = || format!("invalid position \"{}\"", value);
+
+    let value = value.trim().to_uppercase();
+    let ns = value.find(['N', 'S']).ok_or_else(err)?;
+    let (lat, rest) = value.split_at(ns);
+    let (hemi, lon) = rest.split_at(1);
+    let ew = lon.find(['E', 'W']).ok_or_else(err)?;
+    if !lon[ew + 1..].trim().is_empty() {
+        return Err(err());
+    }
+
+    let mut lat = parse_dms(lat).filter(|x| *x <= 90.0).ok_or_else(err)?;
+    let mut lon_deg = parse_dms(&lon[..ew])
+        .filter(|x| *x <= 180.0)
+        .ok_or_else(err)?;
+    if hemi == "S" {
+        lat = -lat;
+    }
+    if &lon[ew..ew + 1] == "W" {
+        lon_deg = -lon_deg;
+    }
+
+    Ok(LatLon { lat, lon: lon_deg })
+}
+
+// Records read within an airspace, other than AC
+const RECORDS: [&str; 15] = [
+    "AN", "AY", "AL", "AH", "AF", "AG", "V", "DP", "DB", "DC", "SP", "SB", "AT", "TO", "TC",
+];
+
+// Read OpenAir data
+pub fn read(text: &str) -> Result<Vec<Feature>, ReadError> {
+    let mut features = Vec::new();
+    let mut airspace: Option<Airspace> = None;
+
+    for (n, line) in text.lines().enumerate() {
+        let err = |message: String| ReadError {
+            line: n + 1,
+            message,
+        };
+
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('*') {
+            continue;
+        }
+
+        let (record, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let record = record.to_uppercase();
+        let value = value.trim();
+
+        // Start of new airspace
+        if record == "AC" {
+            if let Some(airspace) = airspace.take() {
+                features.push(airspace.into_feature()?);
+            }
+            airspace = Some(Airspace {
+                line: n + 1,
+                class: value.to_uppercase(),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        // Only comments and blank lines may come before the first airspace
+        let Some(current) = airspace.as_mut() else {
+            return Err(err(if RECORDS.contains(&record.as_str()) {
+                format!("{} record before first AC record", record)
+            } else {
+                format!("unsupported record \"{}\"", record)
+            }));
+        };
+
+        let result = match record.as_str() {
+            "AN" => {
+                current.name = Some(value.to_string());
+                Ok(())
+            }
+            "AY" => {
+                current.airspace_type = Some(value.to_uppercase());
+                Ok(())
+            }
+            "AL" => parse_level(value).map(|level| current.lower = Some(level)),
+            "AH" => parse_level(value).map(|level| current.upper = Some(level)),
+            "AF" => value
+                .parse()
+                .map(|freq| current.frequency = Some(freq))
+                .map_err(|_| format!("invalid frequency \"{}\"", value)),
+            "AG" => {
+                current.callsign = Some(value.to_string());
+                Ok(())
+            }
+            "V" => match value.split_once('=') {
+                Some((var, dir)) if var.trim().eq_ignore_ascii_case("D") => match dir.trim() {
+                    "+" => Ok(Direction::Cw),
+                    "-" => Ok(Direction::Ccw),
+                    _ => Err(format!("invalid direction \"{}\"", dir)),
+                }
+                .map(|dir| current.dir = Some(dir)),
+                Some((var, centre)) if var.trim().eq_ignore_ascii_case("X") => {
+                    parse_latlon(centre).map(|c| current.centre = Some(c))
+                }
+                // Other variables (e.g. W airway width) aren't supported
+                _ => Ok(()),
+            },
+            "DP" => parse_latlon(value).map(|p| current.add_point(p)),
+            "DB" => match value.split_once(',') {
+                Some((from, to)) => parse_latlon(from)
+                    .and_then(|from| Ok((from, parse_latlon(to)?)))
+                    .and_then(|(from, to)| current.add_arc(from, to)),
+                None => Err(format!("invalid arc \"{}\"", value)),
+            },
+            "DC" => value
+                .parse::<f64>()
+                .ok()
+                .filter(|r| r.is_finite() && *r > 0.0)
+                .ok_or_else(|| format!("invalid radius \"{}\"", value))
+                .and_then(|r| current.add_circle(r)),
+            // Label and style records are ignored
+            "SP" | "SB" | "AT" | "TO" | "TC" => Ok(()),
+            _ => Err(format!("unsupported record \"{}\"", record)),
+        };
+        result.map_err(err)?;
+    }
+
+    if let Some(airspace) = airspace {
+        features.push(airspace.into_feature()?);
+    }
+
+    Ok(features)
+}
@@ -0,0 +1,483 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::yaixm::{feature_keys, gliding_sites, loa_names, rat_names, wave_names, Level, Yaixm};
+
+// Airspace types
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AirType {
+    ClassA,
+    ClassB,
+    ClassC,
+    ClassD,
+    ClassE,
+    ClassF,
+    ClassG,
+    Danger,
+    Cta,
+    Ctr,
+    Gliding,
+    Matz,
+    Other,
+    Prohibited,
+    Restricted,
+    Rmz,
+    Tmz,
+}
+
+// Output format
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Format {
+    OpenAir,
+    OpenAirExtended,
+    RatOnly,
+    Competition,
+    GeoJson,
+    Tnp,
+    Kml,
+    Cub,
+}
+
+// Altutude layer overlay
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Overlay {
+    FL195,
+    FL105,
+    AtzDz,
+}
+
+// Setting values used in the user interface and share links
+const AIRTYPES: [(AirType, &str); 17] = [
+    (AirType::ClassA, "classa"),
+    (AirType::ClassB, "classb"),
+    (AirType::ClassC, "classc"),
+    (AirType::ClassD, "classd"),
+    (AirType::ClassE, "classe"),
+    (AirType::ClassF, "classf"),
+    (AirType::ClassG, "classg"),
+    (AirType::Danger, "danger"),
+    (AirType::Cta, "cta"),
+    (AirType::Ctr, "ctr"),
+    (AirType::Gliding, "gsec"),
+    (AirType::Matz, "matz"),
+    (AirType::Other, "other"),
+    (AirType::Prohibited, "prohibited"),
+    (AirType::Restricted, "restricted"),
+    (AirType::Rmz, "rmz"),
+    (AirType::Tmz, "tmz"),
+];
+
+const FORMATS: [(Format, &str); 8] = [
+    (Format::OpenAir, "openair"),
+    (Format::OpenAirExtended, "openair_ext"),
+    (Format::RatOnly, "ratonly"),
+    (Format::Competition, "competition"),
+    (Format::GeoJson, "geojson"),
+    (Format::Tnp, "tnp"),
+    (Format::Kml, "kml"),
+    (Format::Cub, "cub"),
+];
+
+const OVERLAYS: [(Overlay, &str); 3] = [
+    (Overlay::FL195, "fl195"),
+    (Overlay::FL105, "fl105"),
+    (Overlay::AtzDz, "atzdz"),
+];
+
+// Look up value code
+fn to_code<T: PartialEq>(codes: &[(T, &'static str)], value: &T) -> &'static str {
+    codes
+        .iter()
+        .find(|(v, _)| v == value)
+        .map_or("", |(_, code)| code)
+}
+
+// Look up value from code
+fn from_code<T: Clone>(codes: &[(T, &str)], code: &str) -> Option<T> {
+    codes
+        .iter()
+        .find(|(_, c)| *c == code)
+        .map(|(v, _)| v.clone())
+}
+
+impl AirType {
+    pub fn code(&self) -> &'static str {
+        to_code(&AIRTYPES, self)
+    }
+
+    pub fn from_code(code: &str) -> Option<AirType> {
+        from_code(&AIRTYPES, code)
+    }
+}
+
+impl Format {
+    pub fn code(&self) -> &'static str {
+        to_code(&FORMATS, self)
+    }
+
+    pub fn from_code(code: &str) -> Option<Format> {
+        from_code(&FORMATS, code)
+    }
+}
+
+impl Overlay {
+    pub fn code(&self) -> &'static str {
+        to_code(&OVERLAYS, self)
+    }
+
+    pub fn from_code(code: &str) -> Option<Overlay> {
+        from_code(&OVERLAYS, code)
+    }
+}
+
+// Per-feature override of the airspace type settings
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Override {
+    Include,
+    Exclude,
+}
+
+// Region of interest, latitude/longitude in decimal degrees
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Region {
+    // Centre and radius (nm)
+    Circle {
+        lat: f64,
+        lon: f64,
+        radius: f64,
+    },
+    // Bounding box
+    Bounds {
+        north: f64,
+        south: f64,
+        east: f64,
+        west: f64,
+    },
+    // Uploaded polygon, as (lat, lon) pairs
+    Polygon {
+        name: String,
+        points: Vec<(f64, f64)>,
+    },
+}
+
+impl Region {
+    // Finite coordinates within range, positive radius and non-empty bounds
+    pub fn is_valid(&self) -> bool {
+        let lat_ok = |lat: f64| (-90.0..=90.0).contains(&lat);
+        let lon_ok = |lon: f64| (-180.0..=180.0).contains(&lon);
+
+        match self {
+            Region::Circle { lat, lon, radius } => {
+                lat_ok(*lat) && lon_ok(*lon) && radius.is_finite() && *radius > 0.0
+            }
+            Region::Bounds {
+                north,
+                south,
+                east,
+                west,
+            } => {
+                lat_ok(*north)
+                    && lat_ok(*south)
+                    && lon_ok(*east)
+                    && lon_ok(*west)
+                    && north > south
+                    && east > west
+            }
+            Region::Polygon { points, .. } => {
+                points.len() >= 3 && points.iter().all(|(lat, lon)| lat_ok(*lat) && lon_ok(*lon))
+            }
+        }
+    }
+}
+
+// Current settings schema version
+pub const SETTINGS_VERSION: u64 = 2;
+
+// Schema migrations, MIGRATIONS[n] upgrades version n to n + 1
+const MIGRATIONS: [fn(&mut Map<String, Value>); 2] = [migrate_v0, migrate_v1];
+
+// Settings
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Settings {
+    pub version: u64,
+    pub atz: AirType,
+    pub ils: Option<AirType>,
+    pub unlicensed: Option<AirType>,
+    pub microlight: Option<AirType>,
+    pub gliding: Option<AirType>,
+    pub home: Option<String>,
+    pub hirta_gvs: Option<AirType>,
+    pub obstacle: Option<AirType>,
+    pub max_level: Level,
+    pub min_level: Level,
+    pub clip_upper: bool,
+    pub radio: bool,
+    pub format: Format,
+    pub overlay: Option<Overlay>,
+    pub region: Option<Region>,
+    pub clip_region: bool,
+    #[serde(default)]
+    pub loa: BTreeSet<String>,
+    #[serde(default)]
+    pub rat: BTreeSet<String>,
+    #[serde(default)]
+    pub wave: BTreeSet<String>,
+    // Overrides keyed by feature id (or name if it has no id)
+    #[serde(default)]
+    pub overrides: BTreeMap<String, Override>,
+    // Unrecognised fields, kept so they survive a save
+    #[serde(flatten)]
+    pub unknown: BTreeMap<String, Value>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            version: SETTINGS_VERSION,
+            atz: AirType::Ctr,
+            ils: None,
+            unlicensed: None,
+            microlight: None,
+            gliding: None,
+            home: None,
+            hirta_gvs: None,
+            obstacle: None,
+            max_level: Level::Fl(660),
+            min_level: Level::Surface,
+            clip_upper: false,
+            radio: false,
+            format: Format::OpenAir,
+            overlay: None,
+            region: None,
+            clip_region: false,
+            loa: BTreeSet::new(),
+            rat: BTreeSet::new(),
+            wave: BTreeSet::new(),
+            overrides: BTreeMap::new(),
+            unknown: BTreeMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    // Upgrade stored settings to the current version, using the default
+    // for any field that can't be migrated. Returns a description of each
+    // problem found
+    pub fn migrate(value: Value) -> (Settings, Vec<String>) {
+        let Value::Object(mut stored) = value else {
+            return (
+                Settings::default(),
+                vec!["settings are not a JSON object".to_string()],
+            );
+        };
+
+        let mut warnings = Vec::new();
+
+        // Unversioned settings are version 0
+        let version = stored.get("version").and_then(Value::as_u64).unwrap_or(0);
+        if version > SETTINGS_VERSION {
+            warnings.push(format!(
+                "settings version {} is newer than this application",
+                version
+            ));
+        }
+
+        for migration in MIGRATIONS.iter().skip(version as usize) {
+            migration(&mut stored);
+        }
+        stored.insert("version".to_string(), SETTINGS_VERSION.into());
+
+        // Merge stored fields into defaults, one at a time so a bad value
+        // only loses that field
+        let mut merged = match serde_json::to_value(Settings::default()) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+        for (name, value) in stored {
+            let mut trial = merged.clone();
+            trial.insert(name.clone(), value.clone());
+            if serde_json::from_value::<Settings>(Value::Object(trial)).is_ok() {
+                merged.insert(name, value);
+            } else {
+                warnings.push(format!("\"{}\" setting could not be migrated", name));
+            }
+        }
+
+        let settings = serde_json::from_value(Value::Object(merged)).unwrap_or_default();
+        (settings, warnings)
+    }
+
+    // Remove LOA, RAT, wave box, home site and override names which aren't
+    // in the YAIXM data, returning a description of each one removed
+    pub fn retain_known(&mut self, yaixm: &Yaixm) -> Vec<String> {
+        let mut dropped = Vec::new();
+
+        let mut retain = |set: &mut BTreeSet<String>, names: Vec<String>, kind: &str| {
+            let mut unknown = set
+                .iter()
+                .filter(|n| !names.contains(n))
+                .cloned()
+                .collect::<Vec<String>>();
+            unknown.sort();
+
+            for name in unknown {
+                set.remove(&name);
+                dropped.push(format!("{} \"{}\"", kind, name));
+            }
+        };
+
+        retain(&mut self.loa, loa_names(yaixm), "LOA");
+        retain(&mut self.rat, rat_names(yaixm), "RAT");
+        retain(&mut self.wave, wave_names(yaixm), "Wave box");
+
+        let keys = feature_keys(yaixm);
+        let unknown = self
+            .overrides
+            .keys()
+            .filter(|k| !keys.contains(k))
+            .cloned()
+            .collect::<Vec<String>>();
+        for key in unknown {
+            self.overrides.remove(&key);
+            dropped.push(format!("Override \"{}\"", key));
+        }
+
+        if let Some(home) = &self.home {
+            if !gliding_sites(yaixm).contains(home) {
+                dropped.push(format!("Home site \"{}\"", home));
+                self.home = None;
+            }
+        }
+
+        dropped
+    }
+}
+
+// Version 0 settings predate versioning and are otherwise unchanged
+fn migrate_v0(_settings: &mut Map<String, Value>) {}
+
+// Version 1 maximum level was a flight level number
+fn migrate_v1(settings: &mut Map<String, Value>) {
+    if let Some(fl) = settings.get("max_level").and_then(Value::as_u64) {
+        settings.insert("max_level".to_string(), format!("FL{}", fl).into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture() -> Yaixm {
+        serde_json::from_str(include_str!("../tests/data/yaixm.json")).unwrap()
+    }
+
+    fn names(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn migrate_v0_document() {
+        let (settings, warnings) = Settings::migrate(json!({
+            "atz": "ClassD",
+            "max_level": 105,
+            "radio": true
+        }));
+
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.atz, AirType::ClassD);
+        assert_eq!(settings.max_level, Level::Fl(105));
+        assert!(settings.radio);
+    }
+
+    #[test]
+    fn migrate_unknown_field() {
+        let (settings, warnings) = Settings::migrate(json!({
+            "version": SETTINGS_VERSION,
+            "future_option": [1, 2]
+        }));
+
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(settings.unknown.get("future_option"), Some(&json!([1, 2])));
+
+        // Unknown fields survive a save
+        let value = serde_json::to_value(&settings).unwrap();
+        assert_eq!(value["future_option"], json!([1, 2]));
+    }
+
+    #[test]
+    fn migrate_bad_field() {
+        let (settings, warnings) = Settings::migrate(json!({
+            "version": SETTINGS_VERSION,
+            "atz": "Bogus",
+            "radio": true,
+            "max_level": "FL95"
+        }));
+
+        assert_eq!(warnings, ["\"atz\" setting could not be migrated"]);
+        assert_eq!(settings.atz, Settings::default().atz);
+        assert!(settings.radio);
+        assert_eq!(settings.max_level, Level::Fl(95));
+    }
+
+    #[test]
+    fn retain_known() {
+        let mut settings = Settings {
+            home: Some("OLD SITE".to_string()),
+            loa: names(&["BENSON LOA", "OLD LOA"]),
+            rat: names(&["AIRSHOW", "OLD RAT"]),
+            wave: names(&["NORTH HILL", "OLD WAVE"]),
+            overrides: [
+                ("ctr1".to_string(), Override::Include),
+                ("PROHIB".to_string(), Override::Exclude),
+                ("old1".to_string(), Override::Exclude),
+            ]
+            .into_iter()
+            .collect(),
+            ..Settings::default()
+        };
+
+        let dropped = settings.retain_known(&fixture());
+
+        assert_eq!(
+            dropped,
+            [
+                "LOA \"OLD LOA\"",
+                "RAT \"OLD RAT\"",
+                "Wave box \"OLD WAVE\"",
+                "Override \"old1\"",
+                "Home site \"OLD SITE\"",
+            ]
+        );
+        assert_eq!(settings.loa, names(&["BENSON LOA"]));
+        assert_eq!(settings.rat, names(&["AIRSHOW"]));
+        assert_eq!(settings.wave, names(&["NORTH HILL"]));
+        assert_eq!(
+            settings.overrides.keys().collect::<Vec<_>>(),
+            ["PROHIB", "ctr1"]
+        );
+        assert_eq!(settings.home, None);
+
+        // Known names are kept
+        settings.home = Some("LASHAM".to_string());
+        assert!(settings.retain_known(&fixture()).is_empty());
+        assert_eq!(settings.home.as_deref(), Some("LASHAM"));
+    }
+}
@@ -0,0 +1,426 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use std::collections::BTreeSet;
+
+use asselect::settings::{AirType, Format, Overlay, Override, Region, Settings};
+use asselect::yaixm::Level;
+
+// Value for unset optional settings
+const NONE: &str = "none";
+
+fn airtype_code(value: &Option<AirType>) -> &'static str {
+    value.as_ref().map_or(NONE, AirType::code)
+}
+
+// Percent-encode everything except RFC 3986 unreserved characters
+fn encode_component(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn decode_component(value: &str) -> Option<String> {
+    let mut bytes = Vec::new();
+    let mut iter = value.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+// Sorted, comma separated list of names
+fn encode_set(set: &BTreeSet<String>) -> String {
+    let mut names = set
+        .iter()
+        .map(|n| encode_component(n))
+        .collect::<Vec<String>>();
+    names.sort();
+    names.join(",")
+}
+
+fn decode_set(value: &str) -> Option<BTreeSet<String>> {
+    value
+        .split(',')
+        .filter(|n| !n.is_empty())
+        .map(decode_component)
+        .collect()
+}
+
+// Region as comma separated type and numbers
+fn encode_region(region: &Option<Region>) -> String {
+    let join = |values: &[f64]| {
+        values
+            .iter()
+            .map(f64::to_string)
+            .collect::<Vec<String>>()
+            .join(",")
+    };
+
+    match region {
+        None => NONE.to_string(),
+        Some(Region::Circle { lat, lon, radius }) => {
+            format!("circle,{}", join(&[*lat, *lon, *radius]))
+        }
+        Some(Region::Bounds {
+            north,
+            south,
+            east,
+            west,
+        }) => format!("bounds,{}", join(&[*north, *south, *east, *west])),
+        Some(Region::Polygon { name, points }) => {
+            let coords = points
+                .iter()
+                .flat_map(|(lat, lon)| [*lat, *lon])
+                .collect::<Vec<f64>>();
+            format!("polygon,{},{}", encode_component(name), join(&coords))
+        }
+    }
+}
+
+fn decode_region(value: &str) -> Option<Option<Region>> {
+    if value == NONE {
+        return Some(None);
+    }
+
+    let mut parts = value.split(',');
+    let kind = parts.next()?;
+    let name = if kind == "polygon" {
+        decode_component(parts.next()?)?
+    } else {
+        String::new()
+    };
+    let values = parts
+        .map(|v| v.parse::<f64>().ok())
+        .collect::<Option<Vec<f64>>>()?;
+
+    let region = match (kind, values.as_slice()) {
+        ("circle", [lat, lon, radius]) => Region::Circle {
+            lat: *lat,
+            lon: *lon,
+            radius: *radius,
+        },
+        ("bounds", [north, south, east, west]) => Region::Bounds {
+            north: *north,
+            south: *south,
+            east: *east,
+            west: *west,
+        },
+        ("polygon", coords) if coords.len() >= 6 && coords.len() % 2 == 0 => Region::Polygon {
+            name,
+            points: coords.chunks(2).map(|c| (c[0], c[1])).collect(),
+        },
+        _ => return None,
+    };
+    region.is_valid().then_some(Some(region))
+}
+
+// Encode settings as a URL fragment, omitting default values
+pub fn encode(settings: &Settings) -> String {
+    let default = Settings::default();
+    let mut params = Vec::new();
+
+    let mut add = |name: &str, value: String, default: String| {
+        if value != default {
+            params.push(format!("{}={}", name, value));
+        }
+    };
+
+    add(
+        "atz",
+        settings.atz.code().to_string(),
+        default.atz.code().to_string(),
+    );
+
+    let airtypes = [
+        ("ils", &settings.ils, &default.ils),
+        ("unlicensed", &settings.unlicensed, &default.unlicensed),
+        ("microlight", &settings.microlight, &default.microlight),
+        ("gliding", &settings.gliding, &default.gliding),
+        ("hirta_gvs", &settings.hirta_gvs, &default.hirta_gvs),
+        ("obstacle", &settings.obstacle, &default.obstacle),
+    ];
+    for (name, value, default) in airtypes {
+        add(
+            name,
+            airtype_code(value).to_string(),
+            airtype_code(default).to_string(),
+        );
+    }
+
+    let home = |home: &Option<String>| home.as_deref().map_or(NONE.to_string(), encode_component);
+    add("home", home(&settings.home), home(&default.home));
+
+    let level = |level: &Level| encode_component(&level.to_string());
+    add(
+        "max_level",
+        level(&settings.max_level),
+        level(&default.max_level),
+    );
+    add(
+        "min_level",
+        level(&settings.min_level),
+        level(&default.min_level),
+    );
+    add(
+        "clip_upper",
+        settings.clip_upper.to_string(),
+        default.clip_upper.to_string(),
+    );
+    add(
+        "radio",
+        settings.radio.to_string(),
+        default.radio.to_string(),
+    );
+    add(
+        "format",
+        settings.format.code().to_string(),
+        default.format.code().to_string(),
+    );
+    add(
+        "overlay",
+        settings.overlay.map_or(NONE, |o| o.code()).to_string(),
+        default.overlay.map_or(NONE, |o| o.code()).to_string(),
+    );
+
+    add(
+        "region",
+        encode_region(&settings.region),
+        encode_region(&default.region),
+    );
+    add(
+        "clip_region",
+        settings.clip_region.to_string(),
+        default.clip_region.to_string(),
+    );
+
+    add("loa", encode_set(&settings.loa), encode_set(&default.loa));
+    add("rat", encode_set(&settings.rat), encode_set(&default.rat));
+    add(
+        "wave",
+        encode_set(&settings.wave),
+        encode_set(&default.wave),
+    );
+
+    let overrides = |settings: &Settings, value| {
+        settings
+            .overrides
+            .iter()
+            .filter(|(_, v)| **v == value)
+            .map(|(k, _)| k.clone())
+            .collect::<BTreeSet<String>>()
+    };
+    add(
+        "include",
+        encode_set(&overrides(settings, Override::Include)),
+        encode_set(&overrides(&default, Override::Include)),
+    );
+    add(
+        "exclude",
+        encode_set(&overrides(settings, Override::Exclude)),
+        encode_set(&overrides(&default, Override::Exclude)),
+    );
+
+    params.join("&")
+}
+
+// Decode settings from a URL fragment, ignoring unknown names
+pub fn decode(fragment: &str) -> Result<Settings, String> {
+    let mut settings = Settings::default();
+
+    for param in fragment.split('&').filter(|p| !p.is_empty()) {
+        let (name, value) = param.split_once('=').unwrap_or((param, ""));
+
+        let level = || decode_component(value).and_then(|v| v.parse::<Level>().ok());
+
+        let airtype = || match value {
+            NONE => Some(None),
+            _ => AirType::from_code(value).map(Some),
+        };
+
+        let ok = match name {
+            "atz" => AirType::from_code(value).map(|v| settings.atz = v),
+            "ils" => airtype().map(|v| settings.ils = v),
+            "unlicensed" => airtype().map(|v| settings.unlicensed = v),
+            "microlight" => airtype().map(|v| settings.microlight = v),
+            "gliding" => airtype().map(|v| settings.gliding = v),
+            "hirta_gvs" => airtype().map(|v| settings.hirta_gvs = v),
+            "obstacle" => airtype().map(|v| settings.obstacle = v),
+            "home" => match value {
+                NONE => Some(None),
+                _ => decode_component(value).map(Some),
+            }
+            .map(|v| settings.home = v),
+            "max_level" => level().map(|v| settings.max_level = v),
+            "min_level" => level().map(|v| settings.min_level = v),
+            "clip_upper" => value.parse().ok().map(|v| settings.clip_upper = v),
+            "radio" => value.parse().ok().map(|v| settings.radio = v),
+            "format" => Format::from_code(value).map(|v| settings.format = v),
+            "overlay" => match value {
+                NONE => Some(None),
+                _ => Overlay::from_code(value).map(Some),
+            }
+            .map(|v| settings.overlay = v),
+            "region" => decode_region(value).map(|v| settings.region = v),
+            "clip_region" => value.parse().ok().map(|v| settings.clip_region = v),
+            "loa" => decode_set(value).map(|v| settings.loa = v),
+            "rat" => decode_set(value).map(|v| settings.rat = v),
+            "wave" => decode_set(value).map(|v| settings.wave = v),
+            "include" => decode_set(value).map(|v| {
+                settings
+                    .overrides
+                    .extend(v.into_iter().map(|k| (k, Override::Include)))
+            }),
+            "exclude" => decode_set(value).map(|v| {
+                settings
+                    .overrides
+                    .extend(v.into_iter().map(|k| (k, Override::Exclude)))
+            }),
+            _ => Some(()),
+        };
+
+        if ok.is_none() {
+            return Err(format!("invalid value for \"{}\"", name));
+        }
+    }
+
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(settings: &Settings) {
+        assert_eq!(decode(&encode(settings)).as_ref(), Ok(settings));
+    }
+
+    fn names(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn default_settings() {
+        assert_eq!(encode(&Settings::default()), "");
+        round_trip(&Settings::default());
+    }
+
+    #[test]
+    fn all_settings() {
+        let settings = Settings {
+            atz: AirType::Ctr,
+            ils: Some(AirType::ClassG),
+            unlicensed: None,
+            microlight: Some(AirType::Danger),
+            gliding: None,
+            home: Some("Lasham, Hants & co=1".to_string()),
+            hirta_gvs: Some(AirType::ClassF),
+            obstacle: Some(AirType::ClassG),
+            max_level: Level::Fl(105),
+            min_level: Level::Altitude(1500),
+            clip_upper: true,
+            radio: true,
+            format: Format::Cub,
+            overlay: Some(Overlay::AtzDz),
+            region: Some(Region::Circle {
+                lat: 51.5,
+                lon: -1.25,
+                radius: 30.0,
+            }),
+            clip_region: true,
+            loa: names(&["A,B é", "100% LOA"]),
+            rat: names(&["RAT & co"]),
+            wave: names(&["Wave=1", "Ödön"]),
+            overrides: [
+                ("EG R101".to_string(), Override::Exclude),
+                ("a&b,c".to_string(), Override::Include),
+            ]
+            .into_iter()
+            .collect(),
+            ..Settings::default()
+        };
+
+        round_trip(&settings);
+    }
+
+    #[test]
+    fn regions() {
+        let regions = [
+            Region::Circle {
+                lat: 52.0,
+                lon: 0.5,
+                radius: 12.5,
+            },
+            Region::Bounds {
+                north: 53.0,
+                south: 51.0,
+                east: 1.0,
+                west: -2.5,
+            },
+            Region::Polygon {
+                name: "Área, 50% & more".to_string(),
+                points: vec![(51.0, -1.0), (52.0, -1.0), (52.0, 0.0), (51.0, 0.0)],
+            },
+        ];
+
+        for region in regions {
+            round_trip(&Settings {
+                region: Some(region),
+                ..Settings::default()
+            });
+        }
+    }
+
+    #[test]
+    fn invalid_values() {
+        for fragment in [
+            "atz=none",
+            "ils=foo",
+            "max_level=FL",
+            "clip_upper=yes",
+            "format=txt",
+            "overlay=fl999",
+            "region=circle,52,1",
+            "region=polygon,x,51,1,52,1",
+            "region=bounds,a,b,c,d",
+            "region=circle,NaN,1,10",
+            "region=circle,52,inf,10",
+            "region=circle,52,1,0",
+            "region=circle,52,1,-1",
+            "region=circle,95,1,10",
+            "region=bounds,51,53,1,-2",
+            "region=bounds,53,51,-2,1",
+            "region=polygon,x,51,1,52,1,95,0",
+            "home=%E",
+            "loa=%FF",
+        ] {
+            assert!(decode(fragment).is_err(), "{}", fragment);
+        }
+
+        // Unknown names are ignored
+        assert_eq!(decode("foo=bar"), Ok(Settings::default()));
+    }
+}
@@ -13,109 +13,123 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 //
-use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::rc::Rc;
 use yew::Reducible;
 
-// Airspace types
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub enum AirType {
-    ClassA,
-    ClassB,
-    ClassC,
-    ClassD,
-    ClassE,
-    ClassF,
-    ClassG,
-    Danger,
-    Cta,
-    Ctr,
-    Gliding,
-    Matz,
-    Other,
-    Prohibited,
-    Restricted,
-    Rmz,
-    Tmz,
-}
+use asselect::settings::{AirType, Format, Overlay, Override, Region, Settings};
+use asselect::yaixm::Level;
 
-// Output format
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub enum Format {
-    OpenAir,
-    RatOnly,
-    Competition,
-}
+// Maximum number of undo steps
+const MAX_HISTORY: usize = 50;
 
-// Altutude layer overlay
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub enum Overlay {
-    FL195,
-    FL105,
-    AtzDz,
+// Application state, with settings history for undo/redo
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct State {
+    pub settings: Settings,
+    undo: VecDeque<Settings>,
+    redo: Vec<Settings>,
 }
 
-// Settings
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct Settings {
-    pub atz: AirType,
-    pub ils: Option<AirType>,
-    pub unlicensed: Option<AirType>,
-    pub microlight: Option<AirType>,
-    pub gliding: Option<AirType>,
-    pub home: Option<String>,
-    pub hirta_gvs: Option<AirType>,
-    pub obstacle: Option<AirType>,
-    pub max_level: u16,
-    pub radio: bool,
-    pub format: Format,
-    pub overlay: Option<Overlay>,
-    #[serde(default)]
-    pub loa: HashSet<String>,
-    #[serde(default)]
-    pub rat: HashSet<String>,
-    #[serde(default)]
-    pub wave: HashSet<String>,
-}
+impl State {
+    pub fn new(settings: Settings) -> Self {
+        State {
+            settings,
+            ..Default::default()
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // New state with changed settings, saving the current settings
+    fn update(&self, settings: Settings) -> Self {
+        if settings == self.settings {
+            return self.clone();
+        }
+
+        let mut undo = self.undo.clone();
+        undo.push_back(self.settings.clone());
+        if undo.len() > MAX_HISTORY {
+            undo.pop_front();
+        }
 
-impl Default for Settings {
-    fn default() -> Self {
-        Settings {
-            atz: AirType::Ctr,
-            ils: None,
-            unlicensed: None,
-            microlight: None,
-            gliding: None,
-            home: None,
-            hirta_gvs: None,
-            obstacle: None,
-            max_level: 660,
-            radio: false,
-            format: Format::OpenAir,
-            overlay: None,
-            loa: HashSet::new(),
-            rat: HashSet::new(),
-            wave: HashSet::new(),
+        State {
+            settings,
+            undo,
+            redo: Vec::new(),
         }
     }
-}
 
-// Application state
-#[derive(Debug, Default, PartialEq)]
-pub struct State {
-    pub settings: Settings,
+    // Restore previous settings
+    fn undo(&self) -> Self {
+        let mut state = self.clone();
+        if let Some(settings) = state.undo.pop_back() {
+            state
+                .redo
+                .push(std::mem::replace(&mut state.settings, settings));
+        }
+        state
+    }
+
+    // Restore undone settings
+    fn redo(&self) -> Self {
+        let mut state = self.clone();
+        if let Some(settings) = state.redo.pop() {
+            state
+                .undo
+                .push_back(std::mem::replace(&mut state.settings, settings));
+        }
+        state
+    }
 }
 
 // State actions
 pub enum Action {
-    Set { name: String, value: String },
-    SetLoa { name: String, checked: bool },
-    SetRat { name: String, checked: bool },
-    SetWave { name: String, checked: bool },
+    Load(Box<Settings>),
+    SetAtz(AirType),
+    SetIls(Option<AirType>),
+    SetUnlicensed(Option<AirType>),
+    SetMicrolight(Option<AirType>),
+    SetGliding(Option<AirType>),
+    SetHome(Option<String>),
+    SetHirtaGvs(Option<AirType>),
+    SetObstacle(Option<AirType>),
+    SetMaxLevel(Level),
+    SetMinLevel(Level),
+    SetClipUpper(bool),
+    SetRadio(bool),
+    SetFormat(Format),
+    SetOverlay(Option<Overlay>),
+    SetRegion(Option<Region>),
+    SetClipRegion(bool),
+    SetLoa {
+        name: String,
+        checked: bool,
+    },
+    SetRat {
+        name: String,
+        checked: bool,
+    },
+    SetWave {
+        name: String,
+        checked: bool,
+    },
     ClearLoa,
     ClearRat,
     ClearWave,
+    SetOverride {
+        key: String,
+        value: Option<Override>,
+    },
+    ClearOverrides,
+    Undo,
+    Redo,
 }
 
 impl Reducible for State {
@@ -124,37 +138,28 @@ impl Reducible for State {
     fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
         let mut set = self.settings.clone();
         match action {
-            // Set airspace option
-            Action::Set { name, value } => {
-                match name.as_str() {
-                    "atz" => set.atz = get_airtype(&value).unwrap_or(AirType::Ctr),
-                    "ils" => set.ils = get_airtype(&value),
-                    "unlicensed" => set.unlicensed = get_airtype(&value),
-                    "microlight" => set.microlight = get_airtype(&value),
-                    "gliding" => set.gliding = get_airtype(&value),
-                    "hirta_gvs" => set.hirta_gvs = get_airtype(&value),
-                    "obstacle" => set.obstacle = get_airtype(&value),
-                    "max_level" => set.max_level = value.parse::<u16>().unwrap(),
-                    "radio" => set.radio = value == "yes",
-                    "home" => set.home = if value == "no" { None } else { Some(value) },
-                    "overlay" => {
-                        set.overlay = match value.as_str() {
-                            "fl195" => Some(Overlay::FL195),
-                            "fl105" => Some(Overlay::FL105),
-                            "atzdz" => Some(Overlay::AtzDz),
-                            _ => None,
-                        }
-                    }
-                    "format" => {
-                        set.format = match value.as_str() {
-                            "ratonly" => Format::RatOnly,
-                            "competition" => Format::Competition,
-                            _ => Format::OpenAir,
-                        }
-                    }
-                    _ => (),
-                };
-            }
+            // Settings history
+            Action::Undo => return self.undo().into(),
+            Action::Redo => return self.redo().into(),
+            // Replace all settings
+            Action::Load(settings) => set = *settings,
+            // Set airspace options
+            Action::SetAtz(atz) => set.atz = atz,
+            Action::SetIls(ils) => set.ils = ils,
+            Action::SetUnlicensed(unlicensed) => set.unlicensed = unlicensed,
+            Action::SetMicrolight(microlight) => set.microlight = microlight,
+            Action::SetGliding(gliding) => set.gliding = gliding,
+            Action::SetHome(home) => set.home = home,
+            Action::SetHirtaGvs(hirta_gvs) => set.hirta_gvs = hirta_gvs,
+            Action::SetObstacle(obstacle) => set.obstacle = obstacle,
+            Action::SetMaxLevel(max_level) => set.max_level = max_level,
+            Action::SetMinLevel(min_level) => set.min_level = min_level,
+            Action::SetClipUpper(clip_upper) => set.clip_upper = clip_upper,
+            Action::SetRadio(radio) => set.radio = radio,
+            Action::SetFormat(format) => set.format = format,
+            Action::SetOverlay(overlay) => set.overlay = overlay,
+            Action::SetRegion(region) => set.region = region,
+            Action::SetClipRegion(clip_region) => set.clip_region = clip_region,
             // Include/exclude LOA
             Action::SetLoa { name, checked } => {
                 if checked {
@@ -185,21 +190,198 @@ impl Reducible for State {
             Action::ClearRat => set.rat.clear(),
             // Clear all Wave boxes
             Action::ClearWave => set.wave.clear(),
+            // Set or remove feature override
+            Action::SetOverride { key, value } => match value {
+                Some(value) => {
+                    set.overrides.insert(key, value);
+                }
+                None => {
+                    set.overrides.remove(&key);
+                }
+            },
+            // Clear all feature overrides
+            Action::ClearOverrides => set.overrides.clear(),
         }
-        Self { settings: set }.into()
+        self.update(set).into()
     }
 }
 
-// Default mapping value to airspace type
-fn get_airtype(value: &str) -> Option<AirType> {
-    match value {
-        "classd" => Some(AirType::ClassD),
-        "classf" => Some(AirType::ClassF),
-        "classg" => Some(AirType::ClassG),
-        "ctr" => Some(AirType::Ctr),
-        "danger" => Some(AirType::Danger),
-        "restricted" => Some(AirType::Restricted),
-        "gsec" => Some(AirType::Gliding),
-        _ => None,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reduce(state: &Rc<State>, action: Action) -> Rc<State> {
+        state.clone().reduce(action)
+    }
+
+    fn with_names(state: &Rc<State>) -> Rc<State> {
+        [
+            Action::SetLoa {
+                name: "LOA 1".to_string(),
+                checked: true,
+            },
+            Action::SetRat {
+                name: "RAT 1".to_string(),
+                checked: true,
+            },
+            Action::SetWave {
+                name: "Wave 1".to_string(),
+                checked: true,
+            },
+        ]
+        .into_iter()
+        .fold(state.clone(), |state, action| state.reduce(action))
+    }
+
+    #[test]
+    fn set_atz() {
+        let state = Rc::new(State::default());
+        let state = reduce(&state, Action::SetAtz(AirType::ClassD));
+        assert_eq!(state.settings.atz, AirType::ClassD);
+    }
+
+    #[test]
+    fn set_max_level() {
+        let state = Rc::new(State::default());
+        let state = reduce(&state, Action::SetMaxLevel(Level::Fl(105)));
+        assert_eq!(state.settings.max_level, Level::Fl(105));
+    }
+
+    #[test]
+    fn set_loa() {
+        let state = Rc::new(State::default());
+        let name = || "LOA 1".to_string();
+
+        let state = reduce(
+            &state,
+            Action::SetLoa {
+                name: name(),
+                checked: true,
+            },
+        );
+        assert!(state.settings.loa.contains("LOA 1"));
+
+        let state = reduce(
+            &state,
+            Action::SetLoa {
+                name: name(),
+                checked: false,
+            },
+        );
+        assert!(state.settings.loa.is_empty());
+    }
+
+    #[test]
+    fn clear() {
+        let state = with_names(&Rc::new(State::default()));
+        assert_eq!(state.settings.loa.len(), 1);
+        assert_eq!(state.settings.rat.len(), 1);
+        assert_eq!(state.settings.wave.len(), 1);
+
+        let state = reduce(&state, Action::ClearLoa);
+        assert!(state.settings.loa.is_empty());
+        let state = reduce(&state, Action::ClearRat);
+        assert!(state.settings.rat.is_empty());
+        let state = reduce(&state, Action::ClearWave);
+        assert!(state.settings.wave.is_empty());
+
+        let state = reduce(
+            &state,
+            Action::SetOverride {
+                key: "EG R101".to_string(),
+                value: Some(Override::Exclude),
+            },
+        );
+        let state = reduce(&state, Action::ClearOverrides);
+        assert!(state.settings.overrides.is_empty());
+    }
+
+    #[test]
+    fn set_override() {
+        let state = Rc::new(State::default());
+        let set = |state: &Rc<State>, value| {
+            reduce(
+                state,
+                Action::SetOverride {
+                    key: "EG R101".to_string(),
+                    value,
+                },
+            )
+        };
+
+        let state = set(&state, Some(Override::Include));
+        assert_eq!(
+            state.settings.overrides.get("EG R101"),
+            Some(&Override::Include)
+        );
+
+        let state = set(&state, Some(Override::Exclude));
+        assert_eq!(
+            state.settings.overrides.get("EG R101"),
+            Some(&Override::Exclude)
+        );
+
+        let state = set(&state, None);
+        assert!(state.settings.overrides.is_empty());
+    }
+
+    #[test]
+    fn clear_undoable() {
+        let state = with_names(&Rc::new(State::default()));
+        let names = state.settings.clone();
+
+        for action in [Action::ClearLoa, Action::ClearRat, Action::ClearWave] {
+            let cleared = reduce(&state, action);
+            assert_ne!(cleared.settings, names);
+            assert!(cleared.can_undo());
+
+            let undone = reduce(&cleared, Action::Undo);
+            assert_eq!(undone.settings, names);
+            assert!(undone.can_redo());
+        }
+    }
+
+    #[test]
+    fn change_clears_redo() {
+        let state = Rc::new(State::default());
+        let state = reduce(&state, Action::SetRadio(true));
+        let state = reduce(&state, Action::Undo);
+        assert!(state.can_redo());
+
+        let state = reduce(&state, Action::SetClipUpper(true));
+        assert!(!state.can_redo());
+
+        // Redo with nothing to redo leaves settings unchanged
+        let redone = reduce(&state, Action::Redo);
+        assert_eq!(redone.settings, state.settings);
+        assert!(!redone.settings.radio);
+    }
+
+    #[test]
+    fn no_op_not_saved() {
+        let state = Rc::new(State::default());
+        let atz = state.settings.atz;
+
+        let state = reduce(&state, Action::SetAtz(atz));
+        assert!(!state.can_undo());
+
+        let state = reduce(&state, Action::ClearLoa);
+        assert!(!state.can_undo());
+    }
+
+    #[test]
+    fn history_limit() {
+        let mut state = Rc::new(State::default());
+        for n in 0..MAX_HISTORY + 10 {
+            state = reduce(&state, Action::SetMaxLevel(Level::Fl(n as u16 + 1)));
+        }
+        assert_eq!(state.undo.len(), MAX_HISTORY);
+
+        for _ in 0..MAX_HISTORY {
+            state = reduce(&state, Action::Undo);
+        }
+        assert!(!state.can_undo());
+        assert_eq!(state.settings.max_level, Level::Fl(10));
+        assert_eq!(state.redo.len(), MAX_HISTORY);
     }
 }
@@ -13,7 +13,9 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 //
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 #[derive(Clone, Copy, Deserialize, Debug, Eq, PartialEq)]
 pub enum IcaoClass {
@@ -99,18 +101,242 @@ pub enum Rule {
     Tmz,
 }
 
+// Vertical level
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Level {
+    Surface,
+    // Feet above mean sea level
+    Altitude(u32),
+    // Feet above ground level
+    Height(u32),
+    Fl(u16),
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid level \"{}\"", s);
+        let value = s.trim().to_ascii_uppercase();
+
+        if value == "SFC" {
+            Ok(Level::Surface)
+        } else if let Some(fl) = value.strip_prefix("FL") {
+            fl.trim_start().parse().map(Level::Fl).map_err(|_| err())
+        } else if let Some(hgt) = value.strip_suffix("AGL") {
+            match hgt.trim_end().strip_suffix("FT") {
+                Some(hgt) => hgt.trim_end().parse().map(Level::Height).map_err(|_| err()),
+                None => Err(err()),
+            }
+        } else if let Some(alt) = value.strip_suffix("FT") {
+            alt.trim_end()
+                .parse()
+                .map(Level::Altitude)
+                .map_err(|_| err())
+        } else {
+            Err(err())
+        }
+    }
+}
+
+impl TryFrom<String> for Level {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Level> for String {
+    fn from(value: Level) -> Self {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Level::Surface => write!(f, "SFC"),
+            Level::Altitude(alt) => write!(f, "{} ft", alt),
+            Level::Height(hgt) => write!(f, "{} ft AGL", hgt),
+            Level::Fl(fl) => write!(f, "FL{}", fl),
+        }
+    }
+}
+
+// Position, in decimal degrees
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(try_from = "String")]
+pub struct LatLon {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+// Split decimal degrees into degrees, minutes and seconds
+fn dms(value: f64) -> (u32, u32, u32) {
+    let secs = (value.abs() * 3600.0).round() as u32;
+    (secs / 3600, (secs / 60) % 60, secs % 60)
+}
+
+impl LatLon {
+    // Latitude degrees, minutes, seconds and hemisphere
+    pub fn lat_dms(&self) -> (u32, u32, u32, char) {
+        let (d, m, s) = dms(self.lat);
+        (d, m, s, if self.lat < 0.0 { 'S' } else { 'N' })
+    }
+
+    // Longitude degrees, minutes, seconds and hemisphere
+    pub fn lon_dms(&self) -> (u32, u32, u32, char) {
+        let (d, m, s) = dms(self.lon);
+        (d, m, s, if self.lon < 0.0 { 'W' } else { 'E' })
+    }
+}
+
+impl FromStr for LatLon {
+    type Err = String;
+
+    // Parse DDMMSS[NS] DDDMMSS[EW] format
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid lat/lon \"{}\"", s);
+
+        let parse = |value: &str, deg_len: usize, max_deg: u32| -> Option<f64> {
+            if value.len() != deg_len + 4 || !value.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+
+            let d = value[..deg_len].parse::<u32>().ok()?;
+            let m = value[deg_len..deg_len + 2].parse::<u32>().ok()?;
+            let s = value[deg_len + 2..].parse::<u32>().ok()?;
+            if d > max_deg || m >= 60 || s >= 60 {
+                return None;
+            }
+
+            Some(d as f64 + m as f64 / 60.0 + s as f64 / 3600.0)
+        };
+
+        let (lat, lon) = s.split_once(' ').ok_or_else(err)?;
+        if !lat.is_ascii() || !lon.is_ascii() || lat.is_empty() || lon.is_empty() {
+            return Err(err());
+        }
+
+        let (lat, ns) = lat.split_at(lat.len() - 1);
+        let (lon, ew) = lon.split_at(lon.len() - 1);
+
+        let lat = parse(lat, 2, 90).ok_or_else(err)?;
+        let lon = parse(lon, 3, 180).ok_or_else(err)?;
+
+        let lat = match ns {
+            "N" => lat,
+            "S" => -lat,
+            _ => return Err(err()),
+        };
+        let lon = match ew {
+            "E" => lon,
+            "W" => -lon,
+            _ => return Err(err()),
+        };
+
+        Ok(LatLon { lat, lon })
+    }
+}
+
+impl TryFrom<String> for LatLon {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for LatLon {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (lat_d, lat_m, lat_s, ns) = self.lat_dms();
+        let (lon_d, lon_m, lon_s, ew) = self.lon_dms();
+        write!(
+            f,
+            "{:02}{:02}{:02}{} {:03}{:02}{:02}{}",
+            lat_d, lat_m, lat_s, ns, lon_d, lon_m, lon_s, ew
+        )
+    }
+}
+
+// Horizontal distance
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(try_from = "String")]
+pub enum Distance {
+    Nm(f64),
+    Km(f64),
+}
+
+impl Distance {
+    // Distance in nautical miles
+    pub fn nm(&self) -> f64 {
+        match self {
+            Distance::Nm(nm) => *nm,
+            Distance::Km(km) => km / 1.852,
+        }
+    }
+}
+
+impl FromStr for Distance {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid distance \"{}\"", s);
+
+        let (value, unit) = s.split_once(' ').ok_or_else(err)?;
+        let value = value
+            .parse::<f64>()
+            .ok()
+            .filter(|x| x.is_finite() && *x >= 0.0)
+            .ok_or_else(err)?;
+
+        match unit {
+            "nm" => Ok(Distance::Nm(value)),
+            "km" => Ok(Distance::Km(value)),
+            _ => Err(err()),
+        }
+    }
+}
+
+impl TryFrom<String> for Distance {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Distance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Distance::Nm(nm) => write!(f, "{} nm", nm),
+            Distance::Km(km) => write!(f, "{} km", km),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, Eq, PartialEq)]
+pub enum Direction {
+    #[serde(rename = "cw")]
+    Cw,
+    #[serde(rename = "ccw")]
+    Ccw,
+}
+
 #[derive(Clone, Deserialize, Debug)]
 pub struct Circle {
-    pub centre: String,
-    pub radius: String,
+    pub centre: LatLon,
+    pub radius: Distance,
 }
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Arc {
-    pub centre: String,
-    pub dir: String,
-    pub radius: String,
-    pub to: String,
+    pub centre: LatLon,
+    pub dir: Direction,
+    pub radius: Distance,
+    pub to: LatLon,
 }
 
 #[derive(Clone, Deserialize, Debug)]
@@ -120,20 +346,21 @@ pub enum Boundary {
     #[serde(rename = "arc")]
     Arc(Arc),
     #[serde(rename = "line")]
-    Line(Vec<String>),
+    Line(Vec<LatLon>),
 }
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Volume {
     pub id: Option<String>,
     pub name: Option<String>,
-    pub lower: String,
-    pub upper: String,
+    pub lower: Level,
+    pub upper: Level,
     #[serde(rename = "class")]
     pub icao_class: Option<IcaoClass>,
     pub rules: Option<Vec<Rule>>,
     pub seq: Option<String>,
     pub frequency: Option<f64>,
+    pub callsign: Option<String>,
     pub boundary: Vec<Boundary>,
 }
 
@@ -151,6 +378,13 @@ pub struct Feature {
     pub geometry: Vec<Volume>,
 }
 
+impl Feature {
+    // Identifier for settings, the feature id if it has one else its name
+    pub fn key(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.name)
+    }
+}
+
 #[derive(Clone, Deserialize, Debug)]
 pub struct Replace {
     pub id: String,
@@ -173,9 +407,9 @@ pub struct Loa {
 
 #[derive(Deserialize, Debug)]
 pub struct Obstacle {
-    pub elevation: String,
+    pub elevation: Level,
     pub name: String,
-    pub position: String,
+    pub position: LatLon,
 }
 
 #[derive(Deserialize, Debug)]
@@ -229,6 +463,15 @@ pub fn loa_names(yaixm: &Yaixm) -> Vec<String> {
         .collect::<Vec<String>>()
 }
 
+// List of airspace feature keys
+pub fn feature_keys(yaixm: &Yaixm) -> Vec<String> {
+    yaixm
+        .airspace
+        .iter()
+        .map(|x| x.key().to_string())
+        .collect::<Vec<String>>()
+}
+
 // List of Wave boxes
 pub fn wave_names(yaixm: &Yaixm) -> Vec<String> {
     yaixm
@@ -238,3 +481,91 @@ pub fn wave_names(yaixm: &Yaixm) -> Vec<String> {
         .map(|x| x.name.clone())
         .collect::<Vec<String>>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_level() {
+        for (text, level) in [
+            ("SFC", Level::Surface),
+            ("sfc", Level::Surface),
+            ("FL85", Level::Fl(85)),
+            ("fl 85", Level::Fl(85)),
+            ("4500 ft", Level::Altitude(4500)),
+            (" 4500FT ", Level::Altitude(4500)),
+            ("1500 ft AGL", Level::Height(1500)),
+            ("1500 FT agl", Level::Height(1500)),
+        ] {
+            assert_eq!(text.parse::<Level>(), Ok(level), "{}", text);
+            assert_eq!(level.to_string().parse::<Level>(), Ok(level));
+        }
+
+        for text in ["", "FL", "4500", "4500 m", "ft", "AGL", "FL-1"] {
+            assert!(text.parse::<Level>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_latlon() {
+        let p = "513000N 0011530W".parse::<LatLon>().unwrap();
+        assert_eq!(p.lat, 51.5);
+        assert_eq!(p.lon, -(1.0 + 15.0 / 60.0 + 30.0 / 3600.0));
+        assert_eq!(p.to_string(), "513000N 0011530W");
+
+        let p = "010203S 1790000E".parse::<LatLon>().unwrap();
+        assert!(p.lat < 0.0 && p.lon > 0.0);
+        assert_eq!(p.to_string(), "010203S 1790000E");
+
+        for text in [
+            "513000X 0011530W",
+            "513000N 0011530Q",
+            "516000N 0011530W",
+            "513060N 0011530W",
+            "513000N 0016000E",
+            "513000N 0011560E",
+            "913000N 0011530W",
+            "513000N 1810000E",
+            "51300N 0011530W",
+            "513000N0011530W",
+            "5130a0N 0011530W",
+            "",
+        ] {
+            assert!(text.parse::<LatLon>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn dms_rounding() {
+        assert_eq!(dms(51.5), (51, 30, 0));
+        assert_eq!(dms(-1.0 - 15.0 / 60.0 - 30.0 / 3600.0), (1, 15, 30));
+
+        // 59.9999" rounds up into the next minute and degree
+        assert_eq!(dms(51.0 + 59.0 / 60.0 + 59.9999 / 3600.0), (52, 0, 0));
+        assert_eq!(dms(1.0 + 29.0 / 60.0 + 59.9999 / 3600.0), (1, 30, 0));
+        assert_eq!(dms(1.0 + 29.0 / 60.0 + 59.4 / 3600.0), (1, 29, 59));
+
+        let p = LatLon {
+            lat: -(51.0 + 59.0 / 60.0 + 59.9999 / 3600.0),
+            lon: 0.0,
+        };
+        assert_eq!(p.lat_dms(), (52, 0, 0, 'S'));
+        assert_eq!(p.lon_dms(), (0, 0, 0, 'E'));
+        assert_eq!(p.to_string(), "520000S 0000000E");
+    }
+
+    #[test]
+    fn parse_distance() {
+        assert_eq!("5 nm".parse::<Distance>(), Ok(Distance::Nm(5.0)));
+        assert_eq!("1.852 km".parse::<Distance>(), Ok(Distance::Km(1.852)));
+        assert_eq!(Distance::Nm(2.5).nm(), 2.5);
+        assert!((Distance::Km(1.852).nm() - 1.0).abs() < 1e-12);
+        assert!((Distance::Km(10.0).nm() - 5.399568).abs() < 1e-6);
+        assert_eq!(Distance::Km(2.5).to_string(), "2.5 km");
+
+        for text in ["5", "5nm", "5 mi", "-1 nm", "NaN nm", "inf km", "x km"] {
+            assert!(text.parse::<Distance>().is_err(), "{}", text);
+        }
+    }
+}
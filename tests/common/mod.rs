@@ -0,0 +1,31 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+use std::fs;
+use std::path::PathBuf;
+
+use asselect::yaixm::Yaixm;
+
+// Test data directory
+pub fn test_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+// YAIXM fixture covering each airspace type, local type and rule
+pub fn fixture() -> Yaixm {
+    let text =
+        fs::read_to_string(test_dir().join("data/yaixm.json")).expect("fixture should be readable");
+    serde_json::from_str(&text).expect("fixture should be valid YAIXM")
+}
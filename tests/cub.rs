@@ -0,0 +1,71 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Structure tests for the Naviter CUB generator. Offsets and sizes follow
+// the header and item layout written by convert::cub.
+use asselect::convert::{cub, openair, OutputOptions};
+use asselect::settings::Settings;
+
+mod common;
+use common::fixture;
+
+const SIZE_OF_HEADER: usize = 210;
+const SIZE_OF_ITEM: usize = 43;
+const FLAG_ORIGIN: u8 = 0x81;
+
+fn get_i32(buf: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+#[test]
+fn structure() {
+    let yaixm = fixture();
+    let settings = Settings::default();
+    let options = OutputOptions::reproducible();
+
+    let buf = cub(&yaixm, &settings, &options).expect("conversion should succeed");
+
+    // One item per OpenAir airspace
+    let expected = openair(&yaixm, &settings, &options)
+        .unwrap()
+        .lines()
+        .filter(|line| line.starts_with("AC "))
+        .count();
+    assert!(expected > 0);
+
+    // Header
+    assert_eq!(get_i32(&buf, 154), SIZE_OF_ITEM as i32);
+    let count = get_i32(&buf, 162) as usize;
+    assert_eq!(count, expected);
+    assert_eq!(get_i32(&buf, 198), SIZE_OF_HEADER as i32);
+    let data_start = get_i32(&buf, 202) as usize;
+    assert_eq!(data_start, SIZE_OF_HEADER + SIZE_OF_ITEM * count);
+    assert!(buf.len() > data_start);
+
+    // Each item's data offset points at an origin flag
+    for n in 0..count {
+        let item = SIZE_OF_HEADER + SIZE_OF_ITEM * n;
+        let offset = get_i32(&buf, item + 22) as usize;
+        assert_eq!(buf[data_start + offset], FLAG_ORIGIN, "item {}", n);
+    }
+}
+
+#[test]
+fn short_airac_date() {
+    let mut yaixm = fixture();
+    yaixm.release.airac_date = "2026".to_string();
+
+    assert!(cub(&yaixm, &Settings::default(), &OutputOptions::reproducible()).is_ok());
+}
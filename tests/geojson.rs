@@ -0,0 +1,107 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Tests for the GeoJSON generator. Properties are checked against the map
+// volumes, which share the same naming and filtering
+use asselect::convert::{geojson, map_volumes, OutputOptions};
+use asselect::settings::{Format, Settings};
+use serde_json::Value;
+
+mod common;
+use common::fixture;
+
+fn settings() -> Settings {
+    Settings {
+        format: Format::GeoJson,
+        radio: true,
+        ..Default::default()
+    }
+}
+
+fn features() -> Vec<Value> {
+    let text = geojson(&fixture(), &settings(), &OutputOptions::default())
+        .expect("conversion should succeed");
+    let value: Value = serde_json::from_str(&text).expect("output should be valid JSON");
+
+    assert_eq!(value["type"], "FeatureCollection");
+    value["features"].as_array().unwrap().clone()
+}
+
+// Twice the signed area of a [lon, lat] ring, positive if anticlockwise
+fn signed_area(ring: &[Value]) -> f64 {
+    ring.windows(2)
+        .map(|p| {
+            let (x0, y0) = (p[0][0].as_f64().unwrap(), p[0][1].as_f64().unwrap());
+            let (x1, y1) = (p[1][0].as_f64().unwrap(), p[1][1].as_f64().unwrap());
+            x0 * y1 - x1 * y0
+        })
+        .sum()
+}
+
+#[test]
+fn rings() {
+    let features = features();
+    assert!(!features.is_empty());
+
+    for feature in features {
+        assert_eq!(feature["type"], "Feature");
+        assert_eq!(feature["geometry"]["type"], "Polygon");
+
+        let rings = feature["geometry"]["coordinates"].as_array().unwrap();
+        assert_eq!(rings.len(), 1);
+        let ring = rings[0].as_array().unwrap();
+        let name = &feature["properties"]["name"];
+
+        // Closed, with anticlockwise exterior ring
+        assert!(ring.len() >= 4, "{}", name);
+        assert_eq!(ring.first(), ring.last(), "{}", name);
+        assert!(signed_area(ring) > 0.0, "{}", name);
+    }
+}
+
+#[test]
+fn properties() {
+    let features = features();
+    let volumes = map_volumes(&fixture(), &settings()).unwrap();
+
+    assert_eq!(features.len(), volumes.len());
+    for (feature, volume) in features.iter().zip(volumes.iter()) {
+        let properties = &feature["properties"];
+        assert_eq!(properties["name"], volume.name.as_str());
+        assert_eq!(properties["type"], volume.openair_type);
+        assert_eq!(properties["lower"], volume.lower.to_string());
+        assert_eq!(properties["upper"], volume.upper.to_string());
+        assert_eq!(properties["frequency"].as_f64(), volume.frequency);
+    }
+
+    let find = |name: &str| {
+        features
+            .iter()
+            .map(|f| &f["properties"])
+            .find(|p| p["name"].as_str().unwrap().starts_with(name))
+            .unwrap_or_else(|| panic!("{} not found", name))
+    };
+
+    // Class from volume in preference to feature, radio frequency in names
+    assert_eq!(find("LONDON TMA EAST")["class"], "E");
+    assert_eq!(find("LONDON 124")["class"], "A");
+    assert_eq!(find("PROHIB")["class"], Value::Null);
+
+    let benson = find("BENSON");
+    assert_eq!(benson["frequency"], 120.9);
+    assert_eq!(benson["lower"], "SFC");
+    assert_eq!(benson["upper"], "3000 ft");
+    assert_eq!(find("STANSTED")["rules"][0], "TMZ");
+}
@@ -0,0 +1,126 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Tests for the KML generator, parsing the output as XML
+use asselect::convert::{kml, OutputOptions};
+use asselect::settings::{Format, Settings};
+use roxmltree::{Document, Node};
+
+mod common;
+use common::fixture;
+
+fn settings() -> Settings {
+    Settings {
+        format: Format::Kml,
+        ..Default::default()
+    }
+}
+
+fn child<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Node<'a, 'i> {
+    node.children()
+        .find(|n| n.has_tag_name(name))
+        .unwrap_or_else(|| panic!("no {} in {:?}", name, node.tag_name()))
+}
+
+fn children<'a, 'i>(node: Node<'a, 'i>, name: &'a str) -> impl Iterator<Item = Node<'a, 'i>> {
+    node.children().filter(move |n| n.has_tag_name(name))
+}
+
+// Altitudes of polygon coordinates
+fn altitudes(polygon: Node) -> Vec<f64> {
+    polygon
+        .descendants()
+        .find(|n| n.has_tag_name("coordinates"))
+        .and_then(|n| n.text())
+        .unwrap()
+        .split_whitespace()
+        .map(|c| c.rsplit(',').next().unwrap().parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn styles() {
+    let output = kml(&fixture(), &settings(), &OutputOptions::reproducible()).unwrap();
+    let doc = Document::parse(&output).expect("output should be valid XML");
+    let document = child(doc.root_element(), "Document");
+
+    let colours = children(document, "Style")
+        .map(|s| {
+            let line = child(child(s, "LineStyle"), "color").text().unwrap();
+            let poly = child(child(s, "PolyStyle"), "color").text().unwrap();
+            (s.attribute("id").unwrap(), line, poly)
+        })
+        .collect::<Vec<_>>();
+
+    // KML colours are aabbggrr
+    for (id, line, poly) in [
+        ("A", "ff0000ff", "400000ff"),
+        ("Q", "ff0080ff", "400080ff"),
+        ("MATZ", "ffaa00aa", "40aa00aa"),
+        ("CTR", "ffff0000", "40ff0000"),
+    ] {
+        assert!(colours.contains(&(id, line, poly)), "{}", id);
+    }
+
+    // Every placemark refers to a defined style
+    for placemark in children(document, "Placemark") {
+        let url = child(placemark, "styleUrl").text().unwrap();
+        assert!(colours.iter().any(|(id, _, _)| url == format!("#{}", id)));
+    }
+}
+
+#[test]
+fn placemarks() {
+    let mut yaixm = fixture();
+    let benson = yaixm
+        .airspace
+        .iter_mut()
+        .find(|f| f.name == "BENSON")
+        .unwrap();
+    benson.name = "BENSON & <CO>".to_string();
+
+    let output = kml(&yaixm, &settings(), &OutputOptions::reproducible()).unwrap();
+    let doc = Document::parse(&output).expect("output should be valid XML");
+    let document = child(doc.root_element(), "Document");
+
+    // Header and descriptions are escaped text, not markup
+    assert!(child(document, "description")
+        .text()
+        .unwrap()
+        .starts_with("UK Airspace\n"));
+
+    let placemark = children(document, "Placemark")
+        .find(|p| child(*p, "name").text() == Some("BENSON & <CO> MATZ"))
+        .expect("escaped name should round trip");
+    assert_eq!(
+        child(placemark, "description").text(),
+        Some("Lower: SFC<br/>Upper: 3000 ft<br/>Frequency: 120.900")
+    );
+
+    // Floor, ceiling then walls, all at absolute altitudes (m)
+    let polygons = children(child(placemark, "MultiGeometry"), "Polygon").collect::<Vec<_>>();
+    for polygon in &polygons {
+        assert_eq!(child(*polygon, "altitudeMode").text(), Some("absolute"));
+    }
+
+    let floor = altitudes(polygons[0]);
+    let ceiling = altitudes(polygons[1]);
+    assert!(floor.iter().all(|a| *a == 0.0));
+    assert!(ceiling.iter().all(|a| *a == 914.0));
+    assert_eq!(polygons.len(), 2 + floor.len() - 1);
+    for wall in &polygons[2..] {
+        assert_eq!(altitudes(*wall), [0.0, 0.0, 914.0, 914.0, 0.0]);
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+// Golden file tests for the OpenAir generator. Each test converts the
+// fixture in tests/data with a set of settings and compares the result
+// with tests/golden/<name>.txt. Run with UPDATE_GOLDEN=1 to regenerate the
+// golden files after an intentional change, and review the diff.
+use std::env;
+use std::fs;
+
+use asselect::convert::{openair, OutputOptions};
+use asselect::settings::Settings;
+use serde_json::{json, Value};
+
+mod common;
+use common::{fixture, test_dir};
+
+// Convert fixture with settings (as stored JSON) and compare with golden file
+fn check(name: &str, settings: Value) {
+    let (settings, warnings) = Settings::migrate(settings);
+    assert!(warnings.is_empty(), "settings warnings: {:?}", warnings);
+
+    let output = openair(&fixture(), &settings, &OutputOptions::reproducible())
+        .expect("conversion should succeed");
+
+    let path = test_dir().join("golden").join(format!("{}.txt", name));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &output).expect("golden file should be writable");
+        return;
+    }
+
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing {}, run with UPDATE_GOLDEN=1", path.display()));
+    if output != expected {
+        let line = output
+            .lines()
+            .zip(expected.lines())
+            .position(|(a, b)| a != b)
+            .unwrap_or_else(|| output.lines().count().min(expected.lines().count()));
+        panic!(
+            "{} differs from golden file at line {}\n  got:      {:?}\n  expected: {:?}",
+            name,
+            line + 1,
+            output.lines().nth(line),
+            expected.lines().nth(line)
+        );
+    }
+}
+
+// Default settings, ATZ as CTR and optional types excluded
+#[test]
+fn default_settings() {
+    check("default", json!({}));
+}
+
+// Every optional airspace type included, ATZ as class D
+#[test]
+fn all_types() {
+    check(
+        "all_types",
+        json!({
+            "atz": "ClassD",
+            "ils": "ClassG",
+            "unlicensed": "ClassF",
+            "microlight": "ClassF",
+            "gliding": "Gliding",
+            "hirta_gvs": "Danger",
+            "obstacle": "Other"
+        }),
+    );
+}
+
+// ILS defaulting to ATZ type, optional types as "other"
+#[test]
+fn optional_types_other() {
+    check(
+        "types_other",
+        json!({
+            "atz": "ClassD",
+            "unlicensed": "Other",
+            "microlight": "Other",
+            "gliding": "Other",
+            "hirta_gvs": "Other",
+            "obstacle": "Other"
+        }),
+    );
+}
+
+// Competition format: SI danger areas and intense DZs as prohibited,
+// volumes sequenced
+#[test]
+fn competition() {
+    check(
+        "competition",
+        json!({"format": "Competition", "gliding": "Gliding"}),
+    );
+}
+
+// Home gliding site excluded
+#[test]
+fn home_site() {
+    check("home", json!({"gliding": "Gliding", "home": "LASHAM"}));
+}
+
+// Extended OpenAir with AY and AG records, and frequencies in names
+#[test]
+fn extended_radio() {
+    check(
+        "extended_radio",
+        json!({"format": "OpenAirExtended", "radio": true}),
+    );
+}
+
+// Extended OpenAir honours the airspace type settings
+#[test]
+fn extended_types() {
+    check(
+        "extended_types",
+        json!({
+            "format": "OpenAirExtended",
+            "atz": "ClassD",
+            "ils": "ClassG",
+            "unlicensed": "ClassF",
+            "microlight": "ClassF",
+            "hirta_gvs": "Restricted",
+            "obstacle": "ClassF"
+        }),
+    );
+}
+
+// LOA, RAT and wave box selections
+#[test]
+fn loa_rat_wave() {
+    check(
+        "loa_rat_wave",
+        json!({
+            "loa": ["BENSON LOA"],
+            "rat": ["AIRSHOW"],
+            "wave": ["NORTH HILL"]
+        }),
+    );
+}
+
+// RAT only format
+#[test]
+fn rat_only() {
+    check("rat_only", json!({"format": "RatOnly", "rat": ["AIRSHOW"]}));
+}
+
+// Maximum and minimum levels, with upper limits clipped
+#[test]
+fn levels() {
+    check(
+        "levels",
+        json!({"max_level": "FL105", "min_level": "3000 ft", "clip_upper": true}),
+    );
+}
+
+// Version 1 settings with a numeric maximum level
+#[test]
+fn migrated_max_level() {
+    check("max_level_v1", json!({"version": 1, "max_level": 100}));
+}
+
+// Per-feature overrides by id and by name
+#[test]
+fn overrides() {
+    check(
+        "overrides",
+        json!({
+            "overrides": {
+                "d1": "Exclude",
+                "FLYING FIELD": "Include",
+                "NORTH HILL": "Include"
+            }
+        }),
+    );
+}
+
+// Region of interest, clipped to a bounding box
+#[test]
+fn region() {
+    check(
+        "region",
+        json!({
+            "region": {"Bounds": {"north": 52.5, "south": 51.5, "east": 0.5, "west": -1.5}},
+            "clip_region": true
+        }),
+    );
+}
+
+// Competition format with a region clipping away the first LONDON TMA
+// volume, later volumes keep their sequence letters
+#[test]
+fn competition_region() {
+    check(
+        "competition_region",
+        json!({
+            "format": "Competition",
+            "region": {"Bounds": {"north": 52.0, "south": 51.0, "east": 2.0, "west": 0.4}},
+            "clip_region": true
+        }),
+    );
+}
@@ -0,0 +1,300 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+// OpenAir reader tests, including round trips of the generator output
+use std::fs;
+
+use asselect::convert::{map_volumes, openair, OutputOptions};
+use asselect::openair::read;
+use asselect::settings::{AirType, Format, Settings};
+use asselect::yaixm::{
+    Boundary, Direction, Feature, IcaoClass, IcaoType, LatLon, Level, LocalType, Service, Yaixm,
+};
+
+mod common;
+use common::{fixture, test_dir};
+
+// Settings with every optional airspace type included
+fn all_types(format: Format) -> Settings {
+    Settings {
+        atz: AirType::ClassD,
+        ils: Some(AirType::ClassG),
+        unlicensed: Some(AirType::ClassF),
+        microlight: Some(AirType::ClassF),
+        gliding: Some(AirType::Gliding),
+        hirta_gvs: Some(AirType::Danger),
+        obstacle: Some(AirType::Other),
+        format,
+        ..Default::default()
+    }
+}
+
+// YAIXM data containing just the given features, with a service for each
+// feature's frequency and callsign
+fn yaixm_from(mut features: Vec<Feature>) -> Yaixm {
+    let mut yaixm = fixture();
+    yaixm.rat.clear();
+    yaixm.loa.clear();
+    yaixm.obstacle.clear();
+    yaixm.service.clear();
+
+    for (n, feature) in features.iter_mut().enumerate() {
+        let volume = &feature.geometry[0];
+        if let Some(frequency) = volume.frequency {
+            let id = format!("read{}", n);
+            yaixm.service.push(Service {
+                callsign: volume.callsign.clone().unwrap_or_default(),
+                frequency,
+                controls: vec![id.clone()],
+            });
+            feature.id = Some(id);
+        }
+    }
+    yaixm.airspace = features;
+    yaixm
+}
+
+// Generate OpenAir, read it back and generate again. Names (and for
+// classic OpenAir, types) aren't preserved, everything else should be
+fn round_trip(settings: Settings, ignore: &[&str]) {
+    let options = OutputOptions::default();
+    let first = openair(&fixture(), &settings, &options).unwrap();
+    let features = read(&first).unwrap();
+
+    // Gliding sectors are read as wave boxes, so select them all
+    let mut again = all_types(settings.format.clone());
+    again.wave = features
+        .iter()
+        .filter(|f| f.local_type == Some(LocalType::Glider))
+        .map(|f| f.name.clone())
+        .collect();
+    let second = openair(&yaixm_from(features), &again, &options).unwrap();
+
+    let body = |text: &str| {
+        text.lines()
+            .filter(|l| !l.starts_with('*') && !ignore.iter().any(|i| l.starts_with(i)))
+            .map(String::from)
+            .collect::<Vec<String>>()
+    };
+    let (first, second) = (body(&first), body(&second));
+    assert!(!first.is_empty());
+    for (n, (a, b)) in first.iter().zip(second.iter()).enumerate() {
+        assert_eq!(a, b, "line {} differs", n + 1);
+    }
+    assert_eq!(first.len(), second.len());
+}
+
+#[test]
+fn round_trip_default() {
+    round_trip(Settings::default(), &["AN ", "AC "]);
+}
+
+#[test]
+fn round_trip_all_types() {
+    round_trip(all_types(Format::OpenAir), &["AN ", "AC "]);
+}
+
+// Extended OpenAir has separate class and type records, so types survive
+#[test]
+fn round_trip_extended() {
+    round_trip(all_types(Format::OpenAirExtended), &["AN "]);
+}
+
+// Read features match the generator's own view of the airspace
+#[test]
+fn read_matches_model() {
+    let settings = all_types(Format::OpenAir);
+    let text = openair(&fixture(), &settings, &OutputOptions::default()).unwrap();
+    let features = read(&text).unwrap();
+    let volumes = map_volumes(&fixture(), &settings).unwrap();
+
+    assert_eq!(features.len(), volumes.len());
+    for (feature, volume) in features.iter().zip(volumes.iter()) {
+        let geometry = &feature.geometry[0];
+        assert_eq!(feature.name, volume.name);
+        assert_eq!(geometry.lower, volume.lower);
+        assert_eq!(geometry.upper, volume.upper);
+        assert_eq!(geometry.frequency, volume.frequency);
+    }
+}
+
+// Every golden file can be read, with one feature per airspace name
+#[test]
+fn read_golden_files() {
+    for entry in fs::read_dir(test_dir().join("golden")).unwrap() {
+        let path = entry.unwrap().path();
+        let text = fs::read_to_string(&path).unwrap();
+        let features = read(&text).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        assert_eq!(
+            features.len(),
+            text.lines().filter(|l| l.starts_with("AN ")).count()
+        );
+    }
+}
+
+// Typical third party OpenAir, with comments, labels, lower case records,
+// decimal minutes, explicit arc start and closing points
+const EXTERNAL: &str = "\
+* Test file
+AC R
+AN Range One
+AL GND
+AH 2500ft MSL
+SP 0,1,0,0,255
+TO Range One
+DP 51:30.5 N 001:00:00 W
+DP 51:30:30 N 000:50:00 W
+V D=-
+V X=51:25:00 N 000:50:00 W
+DB 51:30:30 N 000:50:00 W, 51:19:30 N 000:50:00 W
+dp 51:19:30 N 001:00:00 W
+DP 51:30:30 N 001:00:00 W
+
+AC Q
+AN Circle
+AL FL 45
+AH UNL
+AF 129.975
+V X=52:00:00 N 002:00:00 E
+DC 2.5
+";
+
+#[test]
+fn read_external() {
+    let features = read(EXTERNAL).unwrap();
+    assert_eq!(features.len(), 2);
+
+    let range = &features[0];
+    assert_eq!(range.name, "Range One");
+    assert_eq!(range.icao_type, IcaoType::R);
+    let volume = &range.geometry[0];
+    assert_eq!(volume.lower, Level::Surface);
+    assert_eq!(volume.upper, Level::Altitude(2500));
+
+    // Line, arc, then line without the repeated first point
+    let [Boundary::Line(start), Boundary::Arc(arc), Boundary::Line(end)] =
+        volume.boundary.as_slice()
+    else {
+        panic!("unexpected boundary {:?}", volume.boundary);
+    };
+    assert_eq!(start.len(), 2);
+    assert_eq!(
+        start[0],
+        LatLon {
+            lat: 51.0 + 30.5 / 60.0,
+            lon: -1.0
+        }
+    );
+    assert_eq!(arc.dir, Direction::Ccw);
+    assert!((arc.radius.nm() - 5.5).abs() < 0.01);
+    assert_eq!(end.len(), 1);
+
+    let circle = &features[1];
+    assert_eq!(circle.icao_type, IcaoType::D);
+    let volume = &circle.geometry[0];
+    assert_eq!(volume.lower, Level::Fl(45));
+    assert_eq!(volume.upper, Level::Fl(660));
+    assert_eq!(volume.frequency, Some(129.975));
+    let [Boundary::Circle(c)] = volume.boundary.as_slice() else {
+        panic!("unexpected boundary {:?}", volume.boundary);
+    };
+    assert_eq!(
+        c.centre,
+        LatLon {
+            lat: 52.0,
+            lon: 2.0
+        }
+    );
+    assert_eq!(c.radius.nm(), 2.5);
+}
+
+// Classic types map to the closest YAIXM type, extended types use AY
+#[test]
+fn read_types() {
+    let feature = |records: &str| {
+        let text = format!(
+            "{}\nAN X\nAL SFC\nAH FL50\nV X=52:00:00 N 000:00:00 E\nDC 1\n",
+            records
+        );
+        read(&text).unwrap().remove(0)
+    };
+
+    let f = feature("AC D");
+    assert_eq!(
+        (f.icao_type, f.icao_class),
+        (IcaoType::Cta, Some(IcaoClass::D))
+    );
+    let f = feature("AC W");
+    assert_eq!(
+        (f.icao_type, f.local_type),
+        (IcaoType::DOther, Some(LocalType::Glider))
+    );
+    let f = feature("AC MATZ");
+    assert_eq!(
+        (f.icao_type, f.local_type),
+        (IcaoType::Other, Some(LocalType::Matz))
+    );
+    let f = feature("AC CTR");
+    assert_eq!((f.icao_type, f.icao_class), (IcaoType::Ctr, None));
+    let f = feature("AC D\nAY ATZ\nAG TOWER");
+    assert_eq!(
+        (f.icao_type, f.icao_class),
+        (IcaoType::Atz, Some(IcaoClass::D))
+    );
+    assert_eq!(f.geometry[0].callsign.as_deref(), Some("TOWER"));
+    let f = feature("AC UNCLASSIFIED\nAY GLIDING_SECTOR");
+    assert_eq!((f.icao_type, f.icao_class), (IcaoType::DOther, None));
+}
+
+#[test]
+fn read_errors() {
+    let err = read("AC A\nAN X\nAL SFC\nAH 2000 ft\nDP 52:00:00 X 000:00:00 E\n").unwrap_err();
+    assert_eq!(err.line, 5);
+
+    let err = read("AC A\nAN X\nAL SFC\nAH lots\n").unwrap_err();
+    assert_eq!(err.line, 4);
+
+    let err = read("AC A\nAN X\nAL SFC\nDP 52:00:00 N 000:00:00 E\nAC B\n").unwrap_err();
+    assert_eq!(err.line, 1);
+    assert!(err.message.contains("AH"));
+
+    let err = read("AC A\nAN X\nAL SFC\nAH FL50\nDC 2\n").unwrap_err();
+    assert_eq!(err.line, 5);
+
+    let err = read("DP 52:00:00 N 000:00:00 E\n").unwrap_err();
+    assert_eq!(err.line, 1);
+
+    // Only comments and blank lines may come before the first AC record
+    for record in [
+        "DA 5,0,90",
+        "DY 52:00:00 N 000:00:00 E",
+        "AY ATZ",
+        "AF 120.9",
+        "XX 1",
+    ] {
+        let text = format!("* Header\n\n{}\nAC A\n", record);
+        let err = read(&text).unwrap_err();
+        assert_eq!(err.line, 3);
+        assert!(err.message.contains(&record[..2]), "{}", err);
+    }
+
+    // Unsupported geometry records aren't silently dropped
+    for record in ["DA 5,0,90", "DY 52:00:00 N 000:00:00 E", "XX 1"] {
+        let text = format!("AC A\nAN X\nAL SFC\nAH FL50\n{}\n", record);
+        let err = read(&text).unwrap_err();
+        assert_eq!(err.line, 5);
+        assert!(err.message.contains("unsupported"), "{}", err);
+    }
+}
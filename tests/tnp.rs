@@ -0,0 +1,131 @@
+// Copyright 2023, Alan Sparrow
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or (at
+// your option) any later version.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Structure tests for the TNP generator. Each airspace is a block of
+// KEY=value records following a "#" line
+use asselect::convert::{map_volumes, tnp, OutputOptions};
+use asselect::settings::{Format, Settings};
+use asselect::yaixm::{Boundary, Direction, Level, Yaixm};
+
+mod common;
+use common::fixture;
+
+fn settings() -> Settings {
+    Settings {
+        format: Format::Tnp,
+        ..Default::default()
+    }
+}
+
+// Airspace blocks, as lists of records
+fn blocks(yaixm: &Yaixm) -> Vec<Vec<String>> {
+    let output =
+        tnp(yaixm, &settings(), &OutputOptions::reproducible()).expect("conversion should succeed");
+    let (_, body) = output
+        .split_once("INCLUDE=YES\n")
+        .expect("INCLUDE record should follow header");
+
+    body.split("#\n")
+        .filter(|b| !b.is_empty())
+        .map(|b| b.lines().map(String::from).collect())
+        .collect()
+}
+
+fn find<'a>(blocks: &'a [Vec<String>], title: &str) -> &'a [String] {
+    let record = format!("TITLE={}", title);
+    blocks
+        .iter()
+        .find(|b| b.contains(&record))
+        .unwrap_or_else(|| panic!("{} not found", title))
+}
+
+#[test]
+fn structure() {
+    let yaixm = fixture();
+    let blocks = blocks(&yaixm);
+    assert_eq!(
+        blocks.len(),
+        map_volumes(&yaixm, &settings()).unwrap().len()
+    );
+
+    for block in &blocks {
+        for (n, key) in ["TYPE=", "CLASS=", "TITLE="].iter().enumerate() {
+            assert!(block[n].starts_with(key), "{:?}", block);
+        }
+        assert!(block.iter().any(|r| r.starts_with("TOPS=")), "{:?}", block);
+        assert!(block.iter().any(|r| r.starts_with("BASE=")), "{:?}", block);
+    }
+}
+
+#[test]
+fn types_and_levels() {
+    let blocks = blocks(&fixture());
+
+    let tma = find(&blocks, "LONDON TMA EAST");
+    assert_eq!(tma[..2], ["TYPE=CTA/CTR", "CLASS=E"]);
+    assert!(tma.contains(&"TOPS=FL195".to_string()));
+    assert!(tma.contains(&"BASE=3500ALT".to_string()));
+
+    assert_eq!(find(&blocks, "AWY L9")[..2], ["TYPE=AIRWAYS", "CLASS=A"]);
+    assert_eq!(find(&blocks, "PROHIB")[..2], ["TYPE=PROHIBITED", "CLASS=X"]);
+    assert_eq!(find(&blocks, "BRISTOL")[..2], ["TYPE=RMZ", "CLASS=D"]);
+
+    let benson = find(&blocks, "BENSON MATZ");
+    assert_eq!(benson[..2], ["TYPE=MATZ", "CLASS=X"]);
+    assert!(benson.contains(&"RADIO=120.900".to_string()));
+    assert!(benson.contains(&"BASE=SFC".to_string()));
+    assert!(benson.contains(&"CIRCLE RADIUS=5.00 CENTRE=N513700 W0010600".to_string()));
+}
+
+#[test]
+fn heights_and_arcs() {
+    for (dir, record) in [
+        (Direction::Cw, "CLOCKWISE "),
+        (Direction::Ccw, "ANTI-CLOCKWISE "),
+    ] {
+        let mut yaixm = fixture();
+        let ctr = yaixm
+            .airspace
+            .iter_mut()
+            .find(|f| f.name == "LONDON")
+            .expect("fixture should have LONDON CTR");
+        let volume = &mut ctr.geometry[0];
+        volume.upper = Level::Height(1500);
+        for segment in volume.boundary.iter_mut() {
+            if let Boundary::Arc(arc) = segment {
+                arc.dir = dir;
+            }
+        }
+
+        let blocks = blocks(&yaixm);
+        let ctr = find(&blocks, "LONDON");
+        assert!(ctr.contains(&"TOPS=1500AGL".to_string()));
+
+        let arcs = ctr
+            .iter()
+            .filter(|r| r.contains("RADIUS="))
+            .collect::<Vec<&String>>();
+        assert_eq!(arcs.len(), 1);
+        assert!(arcs[0].starts_with(record), "{}", arcs[0]);
+        assert!(arcs[0].contains(" CENTRE=") && arcs[0].contains(" TO="));
+
+        // Closed with a repeat of the first point
+        let points = ctr
+            .iter()
+            .filter(|r| r.starts_with("POINT="))
+            .collect::<Vec<&String>>();
+        assert_eq!(points.first(), points.last());
+    }
+}